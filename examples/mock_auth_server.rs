@@ -0,0 +1,92 @@
+//! A fake login server for testing the launcher's login without the real
+//! control panel. Any username is accepted with the password "password".
+//!
+//! Run it with `cargo run --example mock_auth_server [address]`, then turn on
+//! the login in launcher-settings.json and set its endpoint to
+//! `http://127.0.0.1:8765/login`. Plain HTTP is only accepted for this
+//! computer.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+const DEFAULT_ADDRESS: &str = "127.0.0.1:8765";
+const PASSWORD: &str = "password";
+
+fn main() {
+    let address = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| DEFAULT_ADDRESS.to_string());
+    let listener = match TcpListener::bind(&address) {
+        Ok(listener) => listener,
+        Err(why) => {
+            eprintln!("Could not listen on {address}: {why}");
+            return;
+        }
+    };
+    println!("Mock login server listening on http://{address}/login");
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                if let Err(why) = handle(stream) {
+                    eprintln!("Request failed: {why}");
+                }
+            }
+            Err(why) => eprintln!("Connection failed: {why}"),
+        }
+    }
+}
+
+fn handle(mut stream: TcpStream) -> Result<(), Box<dyn std::error::Error>> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    // Only the request line and the body length matter here
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header)?;
+        let header = header.trim();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse()?;
+            }
+        }
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+
+    let (status, response) = if !request_line.starts_with("POST ") {
+        (
+            "405 Method Not Allowed",
+            r#"{"error":"Use POST"}"#.to_string(),
+        )
+    } else {
+        let request = serde_json::from_slice::<serde_json::Value>(&body)?;
+        let username = request["username"].as_str().unwrap_or_default();
+        let password = request["password"].as_str().unwrap_or_default();
+        println!("Login attempt by {username:?}");
+
+        if !username.is_empty() && password == PASSWORD {
+            let token = format!("mock-token-{username}");
+            ("200 OK", serde_json::json!({ "token": token }).to_string())
+        } else {
+            (
+                "401 Unauthorized",
+                r#"{"error":"Wrong username or password"}"#.to_string(),
+            )
+        }
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{response}",
+        response.len()
+    )?;
+    Ok(())
+}
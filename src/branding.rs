@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+
+/// The contents of branding.json on the patch server, which lets the look of
+/// the launcher change without shipping a new launcher
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BrandingFile {
+    /// Link buttons shown at the bottom of the launcher. When missing, the
+    /// built-in links are shown.
+    pub links: Option<Vec<LinkButton>>,
+    /// Location of a background image, relative to branding.json. When
+    /// missing, the built-in background is shown.
+    pub background: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LinkButton {
+    pub label: String,
+    pub url: String,
+    /// Buttons are shown from the lowest to the highest order
+    #[serde(default)]
+    pub order: i32,
+}
+
+/// Everything needed to show the server's branding in the GUI
+pub struct Branding {
+    /// Link buttons, already sorted by their order
+    pub links: Vec<LinkButton>,
+    /// Image data for the background
+    pub background: Option<Vec<u8>>,
+}
+
+impl LinkButton {
+    fn new(label: &str, url: &str, order: i32) -> Self {
+        Self {
+            label: label.to_string(),
+            url: url.to_string(),
+            order,
+        }
+    }
+
+    /// Whether the link can be opened. Only web links are allowed, so the
+    /// patch server can't start programs on the player's computer.
+    pub fn is_web_link(&self) -> bool {
+        self.url.starts_with("https://") || self.url.starts_with("http://")
+    }
+}
+
+/// The links which are shown until the patch server says otherwise
+pub fn default_links() -> Vec<LinkButton> {
+    vec![
+        LinkButton::new("Control Panel", "https://ecocp.atomixro.com", 0),
+        LinkButton::new("Register", "https://ecocp.atomixro.com/register", 1),
+    ]
+}
@@ -1,9 +1,14 @@
+use std::path::Path;
 use std::process::Command;
 
 fn main() {
     git_hash();
     git_changes();
-    println!("cargo:rustc-rerun-if-changed=.git/HEAD");
+    trusted_keys();
+    // Once any path is listed, Cargo only reruns this for the listed paths
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/index");
+    println!("cargo:rerun-if-changed=src");
 }
 
 /// Optionally emits GIT_HASH containing the full hash of the latest commit.
@@ -54,3 +59,36 @@ fn git_changes() {
     };
     println!("cargo:rustc-env=GIT_CHANGES={}", git_changes);
 }
+
+/// Emits trusted_keys.rs into OUT_DIR, listing every public key found in the
+/// keys directory. Each `.pub` file holds one base64 Ed25519 public key.
+/// Several keys can be present at once so that signing keys can be rotated.
+///
+/// Without keys the launcher still builds, but it rejects every signed file.
+fn trusted_keys() {
+    println!("cargo:rerun-if-changed=keys");
+
+    let out_dir = match std::env::var("OUT_DIR") {
+        Ok(x) => x,
+        Err(_) => return,
+    };
+
+    let mut keys = Vec::<String>::new();
+    if let Ok(entries) = std::fs::read_dir("keys") {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "pub") {
+                if let Ok(key) = std::fs::read_to_string(&path) {
+                    if !key.trim().is_empty() {
+                        keys.push(key.trim().to_string());
+                    }
+                }
+            }
+        }
+    }
+    keys.sort();
+
+    let source = format!("pub const TRUSTED_KEYS: &[&str] = &{keys:?};\n");
+    std::fs::write(Path::new(&out_dir).join("trusted_keys.rs"), source)
+        .expect("Failed to write trusted_keys.rs");
+}
@@ -0,0 +1,313 @@
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::PathBuf;
+
+/// Directory inside the user's config directory used by the launcher
+const CONFIG_DIR: &str = "aeco-launcher";
+const CREDENTIALS_FILE: &str = "credentials.json";
+/// Remembered logins of the extra game clients, by client name
+const CLIENT_CREDENTIALS_FILE: &str = "client-credentials.json";
+const KEYRING_SERVICE: &str = "aeco-launcher";
+
+/// A remembered login
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+/// Saved in the user's config directory. The password itself is only in
+/// this file if the OS keyring could not be used.
+#[derive(Serialize, Deserialize)]
+struct CredentialsFile {
+    username: String,
+    password: StoredPassword,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "storage", rename_all = "lowercase")]
+enum StoredPassword {
+    /// In the OS keyring, under the username
+    Keyring,
+    /// Encrypted with a key derived from this computer and user. This only
+    /// stops the file from being useful when copied elsewhere, so the keyring
+    /// is always tried first.
+    File { nonce: String, ciphertext: String },
+}
+
+fn config_path(file_name: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let config_dir = dirs::config_dir().ok_or("No config directory was found")?;
+    Ok(config_dir.join(CONFIG_DIR).join(file_name))
+}
+
+fn credentials_path() -> Result<PathBuf, Box<dyn Error>> {
+    config_path(CREDENTIALS_FILE)
+}
+
+/// Names the keyring entry of a client's password, so it doesn't clash with
+/// the main login if both use the same account
+fn client_keyring_user(client: &str, username: &str) -> String {
+    format!("{client}/{username}")
+}
+
+/// Gets the remembered login, if there is one. Problems are not fatal, the
+/// player just has to type their login again. This can wait on the OS
+/// keyring, so it shouldn't be called from the GUI.
+pub fn load() -> Option<Credentials> {
+    let path = credentials_path().ok()?;
+    if !path.exists() {
+        return None;
+    }
+
+    let result = read_credentials(&path);
+    if let Err(why) = &result {
+        log::warn!("Could not load remembered login: {why}");
+    }
+    result.ok()
+}
+
+fn read_credentials(path: &PathBuf) -> Result<Credentials, Box<dyn Error>> {
+    let file = serde_json::from_slice::<CredentialsFile>(&std::fs::read(path)?)?;
+    let password = read_password(&file.username, file.password)?;
+
+    Ok(Credentials {
+        username: file.username,
+        password,
+    })
+}
+
+/// Gets a password from where it was stored. `keyring_user` names its entry
+/// in the OS keyring.
+fn read_password(keyring_user: &str, password: StoredPassword) -> Result<String, Box<dyn Error>> {
+    match password {
+        StoredPassword::Keyring => {
+            Ok(keyring::Entry::new(KEYRING_SERVICE, keyring_user)?.get_password()?)
+        }
+        StoredPassword::File { nonce, ciphertext } => {
+            let nonce = base64::decode(nonce)?;
+            let ciphertext = base64::decode(ciphertext)?;
+            let plaintext = local_cipher()
+                .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+                .map_err(|_| "The remembered password could not be decrypted")?;
+            Ok(String::from_utf8(plaintext)?)
+        }
+    }
+}
+
+/// Stores a password in the OS keyring, or encrypted for the credentials
+/// file if the keyring can't be used
+fn store_password(keyring_user: &str, password: &str) -> Result<StoredPassword, Box<dyn Error>> {
+    let keyring_result = keyring::Entry::new(KEYRING_SERVICE, keyring_user)
+        .and_then(|entry| entry.set_password(password));
+
+    match keyring_result {
+        Ok(_) => Ok(StoredPassword::Keyring),
+        Err(why) => {
+            log::warn!("OS keyring is not available, storing login in a file: {why}");
+            let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+            let ciphertext = local_cipher()
+                .encrypt(&nonce, password.as_bytes())
+                .map_err(|_| "The password could not be encrypted")?;
+            Ok(StoredPassword::File {
+                nonce: base64::encode(nonce),
+                ciphertext: base64::encode(ciphertext),
+            })
+        }
+    }
+}
+
+/// Removes a password from the OS keyring, if it was stored there
+fn delete_password(keyring_user: &str, password: &StoredPassword) {
+    if let StoredPassword::Keyring = password {
+        let result = keyring::Entry::new(KEYRING_SERVICE, keyring_user)
+            .and_then(|entry| entry.delete_password());
+        if let Err(why) = result {
+            log::warn!("Could not remove login from OS keyring: {why}");
+        }
+    }
+}
+
+/// Remembers a login, replacing any login which was remembered before
+pub fn save(username: &str, password: &str) -> Result<(), Box<dyn Error>> {
+    forget()?;
+
+    let file = CredentialsFile {
+        username: username.to_string(),
+        password: store_password(username, password)?,
+    };
+
+    let path = credentials_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    write_private(&path, &serde_json::to_vec_pretty(&file)?)?;
+    Ok(())
+}
+
+/// Removes the remembered login, if there is one
+pub fn forget() -> Result<(), Box<dyn Error>> {
+    let path = credentials_path()?;
+    if !path.exists() {
+        return Ok(());
+    }
+
+    // The file should go away even if it can't be read anymore
+    if let Ok(file) = serde_json::from_slice::<CredentialsFile>(&std::fs::read(&path)?) {
+        delete_password(&file.username, &file.password);
+    }
+
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+/// Reads the remembered logins of every client. A missing or broken file
+/// counts as no remembered logins.
+fn read_client_credentials() -> Result<BTreeMap<String, CredentialsFile>, Box<dyn Error>> {
+    let path = config_path(CLIENT_CREDENTIALS_FILE)?;
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    Ok(serde_json::from_slice(&std::fs::read(path)?).unwrap_or_default())
+}
+
+fn write_client_credentials(
+    clients: &BTreeMap<String, CredentialsFile>,
+) -> Result<(), Box<dyn Error>> {
+    let path = config_path(CLIENT_CREDENTIALS_FILE)?;
+    if clients.is_empty() {
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+        return Ok(());
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    write_private(&path, &serde_json::to_vec_pretty(clients)?)
+}
+
+/// Gets the remembered login of a game client, if there is one. Like `load`,
+/// this shouldn't be called from the GUI.
+pub fn load_client(client: &str) -> Option<Credentials> {
+    let result = read_client_credentials().and_then(|mut clients| {
+        let file = match clients.remove(client) {
+            Some(file) => file,
+            None => return Ok(None),
+        };
+        let keyring_user = client_keyring_user(client, &file.username);
+        let password = read_password(&keyring_user, file.password)?;
+        Ok(Some(Credentials {
+            username: file.username,
+            password,
+        }))
+    });
+
+    match result {
+        Ok(credentials) => credentials,
+        Err(why) => {
+            log::warn!("Could not load remembered login of client {client:?}: {why}");
+            None
+        }
+    }
+}
+
+/// Remembers the login of a game client, replacing its previous login
+pub fn save_client(client: &str, username: &str, password: &str) -> Result<(), Box<dyn Error>> {
+    forget_client(client)?;
+
+    let mut clients = read_client_credentials()?;
+    let keyring_user = client_keyring_user(client, username);
+    clients.insert(
+        client.to_string(),
+        CredentialsFile {
+            username: username.to_string(),
+            password: store_password(&keyring_user, password)?,
+        },
+    );
+    write_client_credentials(&clients)
+}
+
+/// Removes the remembered login of a game client, if it has one
+pub fn forget_client(client: &str) -> Result<(), Box<dyn Error>> {
+    let mut clients = read_client_credentials()?;
+    if let Some(file) = clients.remove(client) {
+        delete_password(&client_keyring_user(client, &file.username), &file.password);
+        write_client_credentials(&clients)?;
+    }
+    Ok(())
+}
+
+/// Writes a file which only the current user can read
+fn write_private(path: &PathBuf, data: &[u8]) -> Result<(), Box<dyn Error>> {
+    use std::io::Write;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    options.open(path)?.write_all(data)?;
+    Ok(())
+}
+
+/// Creates the cipher for passwords stored in the credentials file. The key
+/// is derived from things which identify this computer and user. Environment
+/// variables are left out, since they depend on how the launcher was started.
+fn local_cipher() -> ChaCha20Poly1305 {
+    let mut hasher = Sha256::new();
+    hasher.update(KEYRING_SERVICE);
+
+    if let Some(machine_id) = machine_id() {
+        hasher.update(machine_id.trim());
+    }
+    // Keep the values apart, so they can't run into each other
+    hasher.update([0]);
+
+    if let Some(home_dir) = dirs::home_dir() {
+        hasher.update(home_dir.to_string_lossy().as_bytes());
+    }
+
+    ChaCha20Poly1305::new(Key::from_slice(&hasher.finalize()))
+}
+
+/// The ID the OS gave this computer when it was installed
+#[cfg(unix)]
+fn machine_id() -> Option<String> {
+    ["/etc/machine-id", "/var/lib/dbus/machine-id"]
+        .iter()
+        .find_map(|path| std::fs::read_to_string(path).ok())
+}
+
+/// The ID the OS gave this computer when it was installed
+#[cfg(windows)]
+fn machine_id() -> Option<String> {
+    use std::os::windows::process::CommandExt;
+    /// Keeps a console window from flashing up
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    let output = std::process::Command::new("reg")
+        .args([
+            "query",
+            r"HKLM\SOFTWARE\Microsoft\Cryptography",
+            "/v",
+            "MachineGuid",
+        ])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .ok()?;
+
+    // The value is the last word of the line naming it
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find(|line| line.contains("MachineGuid"))
+        .and_then(|line| line.split_whitespace().last())
+        .map(str::to_string)
+}
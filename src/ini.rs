@@ -0,0 +1,527 @@
+use std::error::Error;
+
+/// How an INI file was encoded, so it can be written back the same way
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum IniEncoding {
+    Utf8 {
+        /// Whether the file started with a byte order mark
+        bom: bool,
+    },
+    /// Little endian UTF-16, which the game writes
+    Utf16Le {
+        /// Whether the file started with a byte order mark
+        bom: bool,
+    },
+    /// Big endian UTF-16
+    Utf16Be {
+        /// Whether the file started with a byte order mark
+        bom: bool,
+    },
+}
+
+/// An INI file which can be changed without losing anything the game or the
+/// player wrote in it. Lines the launcher doesn't change are written back
+/// exactly as they were, including comments, ordering and unusual formatting.
+///
+/// Like Windows, section names and keys are not case sensitive, and the
+/// value is everything after the first `=`.
+#[derive(Clone)]
+pub struct IniFile {
+    encoding: IniEncoding,
+    line_ending: &'static str,
+    ends_with_newline: bool,
+    lines: Vec<IniLine>,
+}
+
+#[derive(Clone)]
+struct IniLine {
+    raw: String,
+    kind: LineKind,
+}
+
+#[derive(Clone)]
+enum LineKind {
+    /// `[Name]`
+    Section(String),
+    /// `key=value`. The value starts at `value_start` in the raw line.
+    Entry {
+        key: String,
+        value: String,
+        value_start: usize,
+    },
+    /// Comments, blank lines and anything else
+    Other,
+}
+
+/// A value to change in an INI file
+#[derive(Clone, PartialEq, Eq)]
+pub struct IniChange {
+    pub section: String,
+    pub key: String,
+    pub value: String,
+}
+
+impl IniFile {
+    /// Reads an INI file in any of the encodings in `IniEncoding`
+    pub fn parse(data: &[u8]) -> Result<Self, Box<dyn Error>> {
+        let (text, encoding) = decode(data)?;
+        // The game is a Windows program, so files without any lines get
+        // Windows line endings
+        let line_ending = if text.contains("\r\n") || !text.contains('\n') {
+            "\r\n"
+        } else {
+            "\n"
+        };
+        let ends_with_newline = text.ends_with('\n');
+
+        let body = text.strip_suffix('\n').unwrap_or(&text);
+        let lines = if text.is_empty() {
+            Vec::new()
+        } else {
+            body.split('\n')
+                .map(|line| parse_line(line.strip_suffix('\r').unwrap_or(line)))
+                .collect()
+        };
+
+        Ok(Self {
+            encoding,
+            line_ending,
+            ends_with_newline,
+            lines,
+        })
+    }
+
+    /// Writes the file in the encoding it was read in
+    pub fn to_bytes(&self) -> Vec<u8> {
+        encode(&self.to_text(), self.encoding)
+    }
+
+    /// The contents of the file as text
+    pub fn to_text(&self) -> String {
+        let mut text = self
+            .lines
+            .iter()
+            .map(|line| line.raw.as_str())
+            .collect::<Vec<_>>()
+            .join(self.line_ending);
+        if self.ends_with_newline && !self.lines.is_empty() {
+            text.push_str(self.line_ending);
+        }
+        text
+    }
+
+    pub fn encoding(&self) -> IniEncoding {
+        self.encoding
+    }
+
+    /// Every `key=value` entry in order, with the section it is in. Entries
+    /// before the first section are in the section `""`.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &str, &str)> {
+        let mut section = "";
+        self.lines.iter().filter_map(move |line| match &line.kind {
+            LineKind::Section(name) => {
+                section = name;
+                None
+            }
+            LineKind::Entry { key, value, .. } => Some((section, key.as_str(), value.as_str())),
+            LineKind::Other => None,
+        })
+    }
+
+    /// Gets a value. If the key is in the file more than once, the first one
+    /// counts, as it does for the game.
+    pub fn get(&self, section: &str, key: &str) -> Option<&str> {
+        self.entries()
+            .find(|(s, k, _)| s.eq_ignore_ascii_case(section) && k.eq_ignore_ascii_case(key))
+            .map(|(_, _, value)| value)
+    }
+
+    /// Sets a value, adding the key or section to the end if they are missing
+    pub fn set(&mut self, section: &str, key: &str, value: &str) {
+        if let Some(i) = self.entry_index(section, key) {
+            let line = &mut self.lines[i];
+            if let LineKind::Entry {
+                value: old_value,
+                value_start,
+                ..
+            } = &mut line.kind
+            {
+                line.raw.replace_range(*value_start.., value);
+                *old_value = value.to_string();
+            }
+            return;
+        }
+
+        let entry = format!("{key}={value}");
+        match self.section_end(section) {
+            Some(end) => self.lines.insert(end, parse_line(&entry)),
+            None => self.add_section(section, &[&entry]),
+        }
+    }
+
+    /// Replaces each value for which `replace` returns a new one, given the
+    /// section, key and value. Duplicate keys are all replaced.
+    pub fn replace_values(&mut self, replace: impl Fn(&str, &str, &str) -> Option<String>) {
+        let mut section = String::new();
+        for line in &mut self.lines {
+            match &mut line.kind {
+                LineKind::Section(name) => section = name.clone(),
+                LineKind::Entry {
+                    key,
+                    value,
+                    value_start,
+                } => {
+                    if let Some(new_value) = replace(&section, key, value) {
+                        line.raw.replace_range(*value_start.., &new_value);
+                        *value = new_value;
+                    }
+                }
+                LineKind::Other => {}
+            }
+        }
+    }
+
+    /// Whether the file has a section, which may be empty
+    pub fn has_section(&self, section: &str) -> bool {
+        self.lines.iter().any(
+            |line| matches!(&line.kind, LineKind::Section(name) if name.eq_ignore_ascii_case(section)),
+        )
+    }
+
+    /// Adds a section to the end of the file, followed by the given lines
+    pub fn add_section(&mut self, section: &str, lines: &[&str]) {
+        if self.lines.is_empty() {
+            self.ends_with_newline = true;
+        }
+        // Keep the new section apart from the one before it
+        if self.lines.last().is_some_and(|line| !line.raw.is_empty()) {
+            self.lines.push(parse_line(""));
+        }
+        self.lines.push(parse_line(&format!("[{section}]")));
+        self.lines.extend(lines.iter().map(|line| parse_line(line)));
+    }
+
+    fn entry_index(&self, section: &str, key: &str) -> Option<usize> {
+        let mut in_section = section.is_empty();
+        for (i, line) in self.lines.iter().enumerate() {
+            match &line.kind {
+                LineKind::Section(name) => in_section = name.eq_ignore_ascii_case(section),
+                LineKind::Entry { key: k, .. } if in_section && k.eq_ignore_ascii_case(key) => {
+                    return Some(i)
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Finds where new entries of a section go: after its last entry, so
+    /// blank lines and comments before the next section stay where they are
+    fn section_end(&self, section: &str) -> Option<usize> {
+        let mut in_section = section.is_empty();
+        let mut end = if in_section { Some(0) } else { None };
+        for (i, line) in self.lines.iter().enumerate() {
+            match &line.kind {
+                LineKind::Section(name) => {
+                    in_section = name.eq_ignore_ascii_case(section);
+                    if in_section {
+                        end = Some(i + 1);
+                    }
+                }
+                LineKind::Entry { .. } if in_section => end = Some(i + 1),
+                _ => {}
+            }
+        }
+        end
+    }
+}
+
+fn parse_line(raw: &str) -> IniLine {
+    let trimmed = raw.trim();
+    let kind = if trimmed.starts_with(';') || trimmed.starts_with('#') {
+        LineKind::Other
+    } else if let Some(name) = trimmed.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+        LineKind::Section(name.trim().to_string())
+    } else if let Some(equals) = raw.find('=') {
+        let after = &raw[equals + 1..];
+        let value_start = equals + 1 + (after.len() - after.trim_start().len());
+        LineKind::Entry {
+            key: raw[..equals].trim().to_string(),
+            value: raw[value_start..].trim_end().to_string(),
+            value_start,
+        }
+    } else {
+        LineKind::Other
+    };
+
+    IniLine {
+        raw: raw.to_string(),
+        kind,
+    }
+}
+
+impl IniEncoding {
+    pub fn name(&self) -> &'static str {
+        match self {
+            IniEncoding::Utf8 { bom: false } => "UTF-8",
+            IniEncoding::Utf8 { bom: true } => "UTF-8 with BOM",
+            IniEncoding::Utf16Le { bom: true } => "UTF-16",
+            IniEncoding::Utf16Le { bom: false } => "UTF-16 without BOM",
+            IniEncoding::Utf16Be { bom: true } => "UTF-16 big endian",
+            IniEncoding::Utf16Be { bom: false } => "UTF-16 big endian without BOM",
+        }
+    }
+}
+
+fn decode(data: &[u8]) -> Result<(String, IniEncoding), Box<dyn Error>> {
+    if let Some(rest) = data.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
+        return Ok((
+            String::from_utf8(rest.to_vec())?,
+            IniEncoding::Utf8 { bom: true },
+        ));
+    }
+    if let Some(rest) = data.strip_prefix(&[0xFF, 0xFE]) {
+        return Ok((
+            decode_utf16(rest, u16::from_le_bytes)?,
+            IniEncoding::Utf16Le { bom: true },
+        ));
+    }
+    if let Some(rest) = data.strip_prefix(&[0xFE, 0xFF]) {
+        return Ok((
+            decode_utf16(rest, u16::from_be_bytes)?,
+            IniEncoding::Utf16Be { bom: true },
+        ));
+    }
+
+    // UTF-8 text never has zero bytes in it, but UTF-16 text which is mostly
+    // ASCII has one in nearly every character
+    if let Some(encoding) = guess_utf16(data) {
+        let from_bytes = match encoding {
+            IniEncoding::Utf16Be { .. } => u16::from_be_bytes,
+            _ => u16::from_le_bytes,
+        };
+        if let Ok(text) = decode_utf16(data, from_bytes) {
+            return Ok((text, encoding));
+        }
+    }
+
+    match String::from_utf8(data.to_vec()) {
+        Ok(text) => Ok((text, IniEncoding::Utf8 { bom: false })),
+        Err(_) => Err("The file is not UTF-8 or UTF-16".into()),
+    }
+}
+
+/// Guesses whether a file without a byte order mark is UTF-16 from where its
+/// zero bytes are
+fn guess_utf16(data: &[u8]) -> Option<IniEncoding> {
+    if data.is_empty() || data.len() % 2 != 0 {
+        return None;
+    }
+    let units = data.len() / 2;
+    let zeros_at = |start: usize| {
+        data.iter()
+            .skip(start)
+            .step_by(2)
+            .filter(|b| **b == 0)
+            .count()
+    };
+    let (even_zeros, odd_zeros) = (zeros_at(0), zeros_at(1));
+
+    if odd_zeros * 2 >= units && even_zeros * 10 < units {
+        Some(IniEncoding::Utf16Le { bom: false })
+    } else if even_zeros * 2 >= units && odd_zeros * 10 < units {
+        Some(IniEncoding::Utf16Be { bom: false })
+    } else {
+        None
+    }
+}
+
+fn decode_utf16(data: &[u8], from_bytes: fn([u8; 2]) -> u16) -> Result<String, Box<dyn Error>> {
+    if data.len() % 2 != 0 {
+        return Err("The file has an odd number of bytes for UTF-16".into());
+    }
+    let units = data
+        .chunks_exact(2)
+        .map(|pair| from_bytes([pair[0], pair[1]]));
+    Ok(char::decode_utf16(units).collect::<Result<String, _>>()?)
+}
+
+fn encode(text: &str, encoding: IniEncoding) -> Vec<u8> {
+    match encoding {
+        IniEncoding::Utf8 { bom } => {
+            let mut bytes = if bom {
+                vec![0xEF, 0xBB, 0xBF]
+            } else {
+                Vec::new()
+            };
+            bytes.extend_from_slice(text.as_bytes());
+            bytes
+        }
+        IniEncoding::Utf16Le { bom } => {
+            let mut bytes = if bom { vec![0xFF, 0xFE] } else { Vec::new() };
+            for unit in text.encode_utf16() {
+                bytes.extend(unit.to_le_bytes());
+            }
+            bytes
+        }
+        IniEncoding::Utf16Be { bom } => {
+            let mut bytes = if bom { vec![0xFE, 0xFF] } else { Vec::new() };
+            for unit in text.encode_utf16() {
+                bytes.extend(unit.to_be_bytes());
+            }
+            bytes
+        }
+    }
+}
+
+/// An option in eco.ini which the launcher knows how to edit
+pub struct KnownOption {
+    pub section: &'static str,
+    pub key: &'static str,
+    pub label: &'static str,
+    pub kind: OptionKind,
+}
+
+pub enum OptionKind {
+    /// `0` or `1`
+    Toggle,
+    /// A whole number in a range, such as a volume or a window size
+    Number { min: i64, max: i64 },
+}
+
+/// Options shown with their own controls in the settings. They are only shown
+/// if the game has written them to eco.ini, so the launcher never adds keys
+/// the game might not understand.
+pub const KNOWN_OPTIONS: &[KnownOption] = &[
+    KnownOption {
+        section: "Option",
+        key: "ScreenWidth",
+        label: "Window width",
+        kind: OptionKind::Number {
+            min: 640,
+            max: 7680,
+        },
+    },
+    KnownOption {
+        section: "Option",
+        key: "ScreenHeight",
+        label: "Window height",
+        kind: OptionKind::Number {
+            min: 480,
+            max: 4320,
+        },
+    },
+    KnownOption {
+        section: "Option",
+        key: "WindowMode",
+        label: "Windowed",
+        kind: OptionKind::Toggle,
+    },
+    KnownOption {
+        section: "Option",
+        key: "BGMVolume",
+        label: "Music volume",
+        kind: OptionKind::Number { min: 0, max: 100 },
+    },
+    KnownOption {
+        section: "Option",
+        key: "SEVolume",
+        label: "Sound effect volume",
+        kind: OptionKind::Number { min: 0, max: 100 },
+    },
+];
+
+/// Finds the known option for a key, if there is one
+pub fn known_option(section: &str, key: &str) -> Option<&'static KnownOption> {
+    KNOWN_OPTIONS.iter().find(|option| {
+        option.section.eq_ignore_ascii_case(section) && option.key.eq_ignore_ascii_case(key)
+    })
+}
+
+/// Checks that a value can be written to eco.ini, and that it makes sense
+/// if the option is a known one. Returns why it can't be written otherwise.
+pub fn validate(section: &str, key: &str, value: &str) -> Result<(), String> {
+    if value.contains(['\r', '\n', '\0']) {
+        return Err("Must be a single line".to_string());
+    }
+
+    match known_option(section, key).map(|option| &option.kind) {
+        Some(OptionKind::Toggle) if value != "0" && value != "1" => {
+            Err("Must be 0 or 1".to_string())
+        }
+        Some(OptionKind::Number { min, max }) => match value.trim().parse::<i64>() {
+            Ok(number) if (*min..=*max).contains(&number) => Ok(()),
+            _ => Err(format!("Must be a number from {min} to {max}")),
+        },
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode, IniEncoding, IniFile};
+
+    const CRLF_TEXT: &str = "; Written by the game\r\n[Options]\r\nName=Café\r\nVolume=80\r\n";
+    const LF_TEXT: &str = "; Written by the game\n[Options]\nName=Café\nVolume=80\n";
+
+    /// Reads `text` written in `encoding`, checks that it was read correctly
+    /// and that writing it back gives the same bytes
+    fn assert_round_trip(text: &str, encoding: IniEncoding) {
+        let data = encode(text, encoding);
+        let ini = IniFile::parse(&data).unwrap();
+        assert_eq!(ini.encoding(), encoding);
+        assert_eq!(ini.get("Options", "Name"), Some("Café"));
+        assert_eq!(ini.get("options", "volume"), Some("80"));
+        assert_eq!(ini.to_text(), text);
+        assert_eq!(ini.to_bytes(), data);
+    }
+
+    #[test]
+    fn round_trips_utf8() {
+        assert_round_trip(CRLF_TEXT, IniEncoding::Utf8 { bom: false });
+        assert_round_trip(CRLF_TEXT, IniEncoding::Utf8 { bom: true });
+    }
+
+    #[test]
+    fn round_trips_utf16_le() {
+        assert_round_trip(CRLF_TEXT, IniEncoding::Utf16Le { bom: false });
+        assert_round_trip(CRLF_TEXT, IniEncoding::Utf16Le { bom: true });
+    }
+
+    #[test]
+    fn round_trips_utf16_be() {
+        assert_round_trip(CRLF_TEXT, IniEncoding::Utf16Be { bom: false });
+        assert_round_trip(CRLF_TEXT, IniEncoding::Utf16Be { bom: true });
+    }
+
+    #[test]
+    fn keeps_line_endings() {
+        assert_round_trip(LF_TEXT, IniEncoding::Utf8 { bom: false });
+        assert_round_trip(LF_TEXT, IniEncoding::Utf16Le { bom: true });
+
+        // Changed and added lines use the file's line endings
+        let mut ini = IniFile::parse(LF_TEXT.as_bytes()).unwrap();
+        ini.set("Options", "Volume", "50");
+        ini.set("Options", "Width", "1024");
+        assert_eq!(
+            ini.to_text(),
+            "; Written by the game\n[Options]\nName=Café\nVolume=50\nWidth=1024\n"
+        );
+    }
+
+    #[test]
+    fn keeps_trailing_newline() {
+        let text = CRLF_TEXT.strip_suffix("\r\n").unwrap();
+        assert_round_trip(text, IniEncoding::Utf8 { bom: false });
+        assert_round_trip(text, IniEncoding::Utf16Le { bom: false });
+        let text = LF_TEXT.strip_suffix('\n').unwrap();
+        assert_round_trip(text, IniEncoding::Utf16Be { bom: true });
+    }
+
+    #[test]
+    fn empty_file() {
+        let ini = IniFile::parse(b"").unwrap();
+        assert_eq!(ini.encoding(), IniEncoding::Utf8 { bom: false });
+        assert!(ini.to_bytes().is_empty());
+    }
+}
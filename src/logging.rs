@@ -0,0 +1,216 @@
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Name of the launcher's log in the log directory
+const LOG_FILE: &str = "launcher.log";
+/// Used for logs when the game directory can't be written to
+const DATA_DIR: &str = "aeco-launcher";
+/// Once the log is this big, it is moved aside and a new one is started
+const MAX_LOG_SIZE: u64 = 1024 * 1024;
+/// How many old logs are kept, as `launcher.1.log`, `launcher.2.log` and so on
+const OLD_LOGS: usize = 4;
+/// How many lines are kept in memory for the log viewer
+const RECENT_LINES: usize = 1000;
+
+/// One line of the log
+#[derive(Clone)]
+pub struct LogLine {
+    /// Local time, such as `2024-01-31 23:59:59.123`
+    pub time: String,
+    pub level: Level,
+    /// The module which logged the line
+    pub target: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for LogLine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {:<5} [{}] {}",
+            self.time, self.level, self.target, self.message
+        )
+    }
+}
+
+/// Writes log lines to stderr, to a file which is rotated once it gets too
+/// big, and to memory for the log viewer
+struct Logger {
+    file: Mutex<Option<LogFile>>,
+    recent: Mutex<VecDeque<LogLine>>,
+}
+
+struct LogFile {
+    dir: PathBuf,
+    file: File,
+    size: u64,
+}
+
+static LOGGER: Logger = Logger {
+    file: Mutex::new(None),
+    recent: Mutex::new(VecDeque::new()),
+};
+
+/// Starts logging to `log_dir`. If it can't be written to, such as when the
+/// game is installed somewhere only administrators can write to, the user's
+/// data directory is used instead. Panics are logged too.
+pub fn init(log_dir: &Path) {
+    if log::set_logger(&LOGGER).is_err() {
+        return;
+    }
+    log::set_max_level(LevelFilter::Debug);
+
+    let fallback_dir = dirs::data_local_dir().map(|dir| dir.join(DATA_DIR).join("logs"));
+    let mut failures = Vec::new();
+    for dir in std::iter::once(log_dir.to_path_buf()).chain(fallback_dir) {
+        match LogFile::open(dir.clone()) {
+            Ok(log_file) => {
+                *lock(&LOGGER.file) = Some(log_file);
+                break;
+            }
+            Err(why) => failures.push(format!("Could not log to {}: {why}", dir.display())),
+        }
+    }
+    for failure in failures {
+        log::warn!("{failure}");
+    }
+
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        log::error!("{info}");
+        default_hook(info);
+    }));
+}
+
+/// The directory the log is written to, if it could be opened
+pub fn log_dir() -> Option<PathBuf> {
+    lock(&LOGGER.file)
+        .as_ref()
+        .map(|log_file| log_file.dir.clone())
+}
+
+/// The log files, newest first
+pub fn log_files() -> Vec<PathBuf> {
+    match log_dir() {
+        Some(dir) => (0..=OLD_LOGS)
+            .map(|number| log_path(&dir, number))
+            .filter(|path| path.is_file())
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+/// The most recent lines of the log at `level` or more severe, oldest first
+pub fn recent_lines(level: Level) -> Vec<LogLine> {
+    lock(&LOGGER.recent)
+        .iter()
+        .filter(|line| line.level <= level)
+        .cloned()
+        .collect()
+}
+
+impl LogFile {
+    fn open(dir: PathBuf) -> std::io::Result<Self> {
+        std::fs::create_dir_all(&dir)?;
+        let path = log_path(&dir, 0);
+        if std::fs::metadata(&path).is_ok_and(|metadata| metadata.len() >= MAX_LOG_SIZE) {
+            rotate(&dir)?;
+        }
+
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self { dir, file, size })
+    }
+
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        if self.size >= MAX_LOG_SIZE {
+            match rotate(&self.dir).and_then(|_| Self::open(self.dir.clone())) {
+                Ok(log_file) => *self = log_file,
+                // Keep writing to the big file rather than losing lines, and
+                // try again later
+                Err(_) => self.size = 0,
+            }
+        }
+        writeln!(self.file, "{line}")?;
+        self.size += line.len() as u64 + 1;
+        Ok(())
+    }
+}
+
+/// Moves each log to the next number, dropping the oldest
+fn rotate(dir: &Path) -> std::io::Result<()> {
+    for number in (0..OLD_LOGS).rev() {
+        let from = log_path(dir, number);
+        if from.is_file() {
+            std::fs::rename(from, log_path(dir, number + 1))?;
+        }
+    }
+    Ok(())
+}
+
+/// `launcher.log` for the current log, or `launcher.N.log` for older ones
+fn log_path(dir: &Path, number: usize) -> PathBuf {
+    match number {
+        0 => dir.join(LOG_FILE),
+        _ => dir.join(format!("launcher.{number}.log")),
+    }
+}
+
+/// Locks a mutex even if a thread panicked while holding it, so a panic can
+/// still be logged
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        // Libraries log a lot at the lower levels
+        if metadata.target().starts_with(env!("CARGO_CRATE_NAME")) {
+            metadata.level() <= Level::Debug
+        } else {
+            metadata.level() <= Level::Warn
+        }
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let line = LogLine {
+            time: chrono::Local::now()
+                .format("%Y-%m-%d %H:%M:%S%.3f")
+                .to_string(),
+            level: record.level(),
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+        };
+        let text = line.to_string();
+
+        // Still useful when the launcher is started from a terminal
+        eprintln!("{text}");
+
+        if let Some(log_file) = lock(&self.file).as_mut() {
+            // There is nowhere left to report this
+            let _ = log_file.write_line(&text);
+        }
+
+        let mut recent = lock(&self.recent);
+        if recent.len() >= RECENT_LINES {
+            recent.pop_front();
+        }
+        recent.push_back(line);
+    }
+
+    fn flush(&self) {
+        if let Some(log_file) = lock(&self.file).as_mut() {
+            let _ = log_file.file.flush();
+        }
+    }
+}
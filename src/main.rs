@@ -1,21 +1,41 @@
 // Don't open a command prompt on Windows
 #![windows_subsystem = "windows"]
 
+mod branding;
+mod credentials;
+mod ini;
+mod logging;
 mod message;
+mod news;
 mod patcher;
+mod runner;
+mod settings;
 mod ui;
 mod version;
 use message::{GUIMessage, PatchMessage};
 use std::sync::mpsc::channel;
 
 fn main() {
+    // Logs are kept with the game, so they are easy to find
+    if let Some(exe_dir) = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.to_path_buf()))
+    {
+        logging::init(&exe_dir.join(patcher::LOG_DIR));
+    }
+    log::info!(
+        "Starting launcher {} on {}",
+        version::version_summary(),
+        patcher::get_platform()
+    );
+
     let (gui_tx, gui_rx) = channel::<GUIMessage>();
     let (patch_tx, patch_rx) = channel::<PatchMessage>();
 
     let patchworker = match patcher::PatchWorker::new(patch_tx, gui_rx) {
         Ok(patchworker) => patchworker,
         Err(why) => {
-            eprintln!("Could not initialize patch worker: {why}");
+            log::error!("Could not initialize patch worker: {why}");
             return;
         }
     };
@@ -28,9 +48,10 @@ fn main() {
     match patchworker.check_patcher_aecoupdate() {
         Ok(patcher::RunState::Close) => return,
         Ok(patcher::RunState::Continue) => {}
-        Err(why) => eprintln!("{:?}", why.internal_error),
+        Err(why) => log::error!("{}: {:?}", why.friendly_message, why.internal_error),
     }
 
+    let use_login = patchworker.settings.login.enabled;
     std::thread::spawn(move || patchworker.run());
-    ui::PatcherUI::run(gui_tx, patch_rx, false);
+    ui::PatcherUI::run(gui_tx, patch_rx, use_login);
 }
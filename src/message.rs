@@ -1,19 +1,181 @@
+use crate::branding::Branding;
+use crate::credentials::Credentials;
+use crate::ini::{IniChange, IniFile};
+use crate::news::News;
+use crate::runner::Runner;
+use crate::settings::Settings;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
 pub enum PatchMessage {
     Error(String),
     Downloading(String, f32),
     Info(String),
+    /// Shows a warning banner with the given text, or hides it if `None`
+    Warning(Option<String>),
+    ServerNotice(ServerNotice),
+    News(News),
+    Branding(Branding),
+    /// Draws the player's attention to the launcher with the given text
+    Notify(String),
     PatchStatus(PatchStatus),
+    /// The current settings, sent once at startup for the settings window
+    Settings(Box<Settings>),
+    /// Runners which were found, for the runner picker
+    Runners(Vec<Runner>),
+    /// Results of the checks made before starting the game
+    Diagnostics(Vec<Check>),
+    /// Logging in failed, with a message for the player
+    LoginError(String),
+    /// The remembered login was refused by the login server, so it was removed
+    LoginForgotten,
+    /// Hides the launcher window while the game runs, or shows it again
+    HideWindow(bool),
+    /// The game exited with an error
+    GameCrashed(GameCrash),
+    /// Names of the extra game clients which are running
+    RunningClients(Vec<String>),
+    /// The game's eco.ini for the settings window, or why it can't be edited
+    GameIni(Result<Box<IniFile>, String>),
+    /// Names of the saved eco.ini profiles
+    IniProfiles(Vec<String>),
+    /// Backups of eco.ini which can be restored, newest first
+    IniBackups(Vec<IniBackup>),
+    /// Diagnostics for support were saved to this file
+    DiagnosticsExported(PathBuf),
+    /// Details of the last error, sent after its message
+    ErrorDetails(ErrorDetails),
+    /// The remembered login, loaded when the launcher starts
+    SavedLogin(Credentials),
+    /// Remembered logins of the extra game clients, by client name
+    SavedClientLogins(Vec<(String, Credentials)>),
+}
+
+/// Information from the server status which is shown to the player
+pub struct ServerNotice {
+    /// Message of the day
+    pub motd: Option<String>,
+    pub maintenance_start: Option<SystemTime>,
+    pub maintenance_end: Option<SystemTime>,
+}
+
+/// Details of a game crash for the player
+pub struct GameCrash {
+    /// The game client which crashed, or `None` for the main game
+    pub client: Option<String>,
+    /// How the game exited, such as `exit code 3`
+    pub exit: String,
+    /// Whether the game crashed while it was still starting
+    pub while_starting: bool,
+    /// Where the output of the game was saved
+    pub log_path: PathBuf,
+}
+
+/// What went wrong, for the player to pass on to support
+#[derive(Clone)]
+pub struct ErrorDetails {
+    /// Stable code for the kind of failure, such as `E101`
+    pub code: String,
+    /// The message shown in the progress bar
+    pub message: String,
+    /// The error and each error which caused it, outermost first
+    pub causes: Vec<String>,
+    /// What the player can try to fix the problem
+    pub remedy: String,
+}
+
+impl std::fmt::Display for ErrorDetails {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Error {}: {}", self.code, self.message)?;
+        writeln!(f, "Launcher: {}", crate::version::version_summary())?;
+        for (depth, cause) in self.causes.iter().enumerate() {
+            match depth {
+                0 => writeln!(f, "Cause: {cause}")?,
+                _ => writeln!(f, "{:indent$}caused by: {cause}", "", indent = depth * 2)?,
+            }
+        }
+        write!(f, "Suggested fix: {}", self.remedy)
+    }
+}
+
+/// A saved copy of eco.ini
+#[derive(Clone)]
+pub struct IniBackup {
+    /// File name in the backup directory
+    pub name: String,
+    /// When the backup was made, for the player
+    pub saved: String,
+}
+
+/// The result of one check made before starting the game
+pub struct Check {
+    pub name: String,
+    pub status: CheckStatus,
+    /// What was found
+    pub detail: String,
+    /// How the player can fix a problem
+    pub remedy: Option<String>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Passed,
+    /// The game can probably start, but something may not work
+    Warning,
+    /// The game can't start until this is fixed
+    Failed,
 }
 
 pub enum PatchStatus {
     Finished,
     Working,
+    Paused,
+    /// The patch server could not be reached, but the game can still be
+    /// played as it was last patched
+    Offline,
     Error,
+    /// The game is running, so it can't be started again
+    Playing,
     Close,
 }
 
 pub enum GUIMessage {
     Retry,
     Play,
+    /// Log in, then start the game
+    Login {
+        username: String,
+        password: String,
+        /// Remember the login for next time if it succeeds
+        remember: bool,
+    },
+    /// Remove the remembered login
+    ForgetLogin,
+    Pause,
+    Resume,
+    Cancel,
+    /// Log in as one of the extra game clients from the settings and start it.
+    /// The password is ignored when login is turned off.
+    StartClient {
+        name: String,
+        password: String,
+        /// Remember the client's login for next time if it succeeds
+        remember: bool,
+    },
+    /// Replace the settings and save them
+    SaveSettings(Box<Settings>),
+    /// Change values in the game's eco.ini
+    SaveGameIni(Vec<IniChange>),
+    /// Put a backup of eco.ini back in place, by its file name
+    RestoreGameIni(String),
+    /// Save the current eco.ini as a profile with the given name
+    SaveIniProfile(String),
+    DeleteIniProfile(String),
+    /// Check whether the game can start, without starting it
+    RunDiagnostics,
+    /// Save logs, settings and checks of the game files to a file for support
+    ExportDiagnostics,
+    /// Delete the game's Wine prefix, so it is set up again from scratch
+    ResetWinePrefix,
     Close,
 }
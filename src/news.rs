@@ -0,0 +1,215 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The contents of news.json on the patch server
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NewsFile {
+    pub items: Vec<NewsItem>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct NewsItem {
+    /// Stays the same for the lifetime of the item, so the launcher can tell
+    /// which items the player has already seen
+    pub id: String,
+    pub title: String,
+    /// When the item was posted, in seconds since the Unix epoch
+    #[serde(default)]
+    pub date: Option<i64>,
+    /// The text of the item, in a small subset of Markdown. See `parse_body`.
+    #[serde(default)]
+    pub body: String,
+    /// Whether the item was posted since the last time the launcher ran
+    #[serde(skip)]
+    pub is_new: bool,
+}
+
+/// Everything needed to show the news in the GUI
+pub struct News {
+    pub items: Vec<NewsItem>,
+    /// Image data by the image location used in the item bodies
+    pub images: HashMap<String, Vec<u8>>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Block {
+    /// `# Heading`, `## Heading`, and so on
+    Heading(String),
+    /// `- Item` or `* Item`
+    Bullet(Vec<Inline>),
+    /// `![description](image.png)` on a line of its own
+    Image(String),
+    /// Any other line
+    Paragraph(Vec<Inline>),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Inline {
+    Text(String),
+    /// `[text](https://example.com)`. Only web links are kept, so news can't
+    /// start programs.
+    Link {
+        text: String,
+        url: String,
+    },
+}
+
+/// Splits an item body into blocks, one for each non-empty line
+pub fn parse_body(body: &str) -> Vec<Block> {
+    body.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            if line.starts_with('#') {
+                Block::Heading(line.trim_start_matches('#').trim().to_string())
+            } else if let Some(item) = line.strip_prefix("- ").or(line.strip_prefix("* ")) {
+                Block::Bullet(parse_inline(item))
+            } else if let Some((_, src)) = line.strip_prefix('!').and_then(parse_link) {
+                Block::Image(src.to_string())
+            } else {
+                Block::Paragraph(parse_inline(line))
+            }
+        })
+        .collect()
+}
+
+/// Gets the location of every image in an item body
+pub fn image_sources(body: &str) -> Vec<String> {
+    parse_body(body)
+        .into_iter()
+        .filter_map(|block| match block {
+            Block::Image(src) => Some(src),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Splits a line of text into plain text and links. Links to anything but
+/// web pages are kept as just their text.
+fn parse_inline(mut line: &str) -> Vec<Inline> {
+    let mut inlines = Vec::new();
+
+    while let Some(start) = line.find('[') {
+        match parse_link(&line[start..]) {
+            Some((text, url)) => {
+                if start > 0 {
+                    inlines.push(Inline::Text(line[..start].to_string()));
+                }
+                if url.starts_with("https://") || url.starts_with("http://") {
+                    inlines.push(Inline::Link {
+                        text: text.to_string(),
+                        url: url.to_string(),
+                    });
+                } else {
+                    inlines.push(Inline::Text(text.to_string()));
+                }
+
+                // Continue after the closing parenthesis
+                let link_len = text.len() + url.len() + 4;
+                line = &line[start + link_len..];
+            }
+            None => {
+                // Not a link, so keep the bracket as text
+                inlines.push(Inline::Text(line[..=start].to_string()));
+                line = &line[start + 1..];
+            }
+        }
+    }
+
+    if !line.is_empty() {
+        inlines.push(Inline::Text(line.to_string()));
+    }
+
+    inlines
+}
+
+/// Parses `[text](url)` at the start of `s`, returning the text and the URL
+fn parse_link(s: &str) -> Option<(&str, &str)> {
+    let rest = s.strip_prefix('[')?;
+    let text_end = rest.find("](")?;
+    let text = &rest[..text_end];
+    if text.contains(['[', ']']) {
+        return None;
+    }
+    let rest = &rest[text_end + 2..];
+    let url_end = rest.find(')')?;
+    Some((text, &rest[..url_end]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_body, parse_inline, Block, Inline};
+
+    fn text(text: &str) -> Inline {
+        Inline::Text(text.to_string())
+    }
+
+    fn link(text: &str, url: &str) -> Inline {
+        Inline::Link {
+            text: text.to_string(),
+            url: url.to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_blocks() {
+        let body = "## Patch notes\n\n- [Forum](https://example.com/forum)\n![Banner](banner.png)\nThanks!";
+        assert_eq!(
+            parse_body(body),
+            [
+                Block::Heading("Patch notes".to_string()),
+                Block::Bullet(vec![link("Forum", "https://example.com/forum")]),
+                Block::Image("banner.png".to_string()),
+                Block::Paragraph(vec![text("Thanks!")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_unterminated_markup_as_text() {
+        assert_eq!(parse_inline("a [b"), [text("a ["), text("b")]);
+        assert_eq!(
+            parse_inline("[b](https://x"),
+            [text("["), text("b](https://x")]
+        );
+        assert_eq!(parse_inline("["), [text("[")]);
+        assert_eq!(
+            parse_body("![Banner](banner.png"),
+            [Block::Paragraph(vec![
+                text("!["),
+                text("Banner](banner.png")
+            ])]
+        );
+    }
+
+    #[test]
+    fn drops_links_which_are_not_web_pages() {
+        assert_eq!(parse_inline("[Run](file:///C:/eco.exe)"), [text("Run")]);
+        assert_eq!(
+            parse_inline("Go [here](javascript:alert(1))"),
+            [text("Go "), text("here"), text(")")]
+        );
+        assert_eq!(
+            parse_inline("[Site](http://example.com)"),
+            [link("Site", "http://example.com")]
+        );
+    }
+
+    #[test]
+    fn handles_multibyte_text_around_markup() {
+        assert_eq!(
+            parse_inline("é[ü](https://example.com/ß)ñ"),
+            [text("é"), link("ü", "https://example.com/ß"), text("ñ")]
+        );
+        assert_eq!(parse_inline("日本[語"), [text("日本["), text("語")]);
+        assert_eq!(
+            parse_inline("[日本](https://例え.jp)"),
+            [link("日本", "https://例え.jp")]
+        );
+        assert_eq!(
+            parse_body("# 🎉 Event"),
+            [Block::Heading("🎉 Event".to_string())]
+        );
+    }
+}
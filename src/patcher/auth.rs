@@ -0,0 +1,139 @@
+use super::transport;
+use crate::settings::Settings;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::net::IpAddr;
+use std::time::Duration;
+
+/// A logged in player
+pub struct Session {
+    pub username: String,
+    /// Session token from the login server, which the game uses to log in
+    pub token: String,
+}
+
+#[derive(Debug)]
+pub enum LoginError {
+    /// The login server refused the username or password. The message is
+    /// shown to the player.
+    Rejected(String),
+    /// The login server could not be reached or sent something unexpected
+    Failed(Box<dyn Error>),
+}
+
+impl std::fmt::Display for LoginError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoginError::Rejected(message) => write!(f, "Login was rejected: {message}"),
+            LoginError::Failed(why) => write!(f, "Login failed: {why}"),
+        }
+    }
+}
+
+impl Error for LoginError {}
+
+#[derive(Serialize)]
+struct LoginRequest<'a> {
+    username: &'a str,
+    password: &'a str,
+}
+
+#[derive(Deserialize)]
+struct LoginResponse {
+    token: String,
+}
+
+/// Sent with an unsuccessful response
+#[derive(Deserialize)]
+struct LoginErrorResponse {
+    error: String,
+}
+
+/// Logs in to the control panel's login endpoint. The endpoint receives a
+/// JSON POST with `username` and `password`, and answers with `{"token": ...}`,
+/// or with 401 or 403 and optionally `{"error": ...}` if the login is refused.
+pub fn login(settings: &Settings, username: &str, password: &str) -> Result<Session, LoginError> {
+    let (status, body) = send_login(settings, username, password).map_err(LoginError::Failed)?;
+
+    match status {
+        _ if status.is_success() => {
+            let response = serde_json::from_slice::<LoginResponse>(&body)
+                .map_err(|why| LoginError::Failed(why.into()))?;
+            Ok(Session {
+                username: username.to_string(),
+                token: response.token,
+            })
+        }
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+            let message = serde_json::from_slice::<LoginErrorResponse>(&body)
+                .map(|response| response.error)
+                .unwrap_or_else(|_| "Wrong username or password".to_string());
+            Err(LoginError::Rejected(message))
+        }
+        _ => Err(LoginError::Failed(
+            format!("Login request failed: {status}").into(),
+        )),
+    }
+}
+
+/// Parses the login endpoint, making sure the password is never sent over an
+/// unencrypted connection. Plain HTTP is only allowed to this computer, for
+/// testing against a mock server.
+fn login_endpoint(endpoint: &str) -> Result<Url, Box<dyn Error>> {
+    if endpoint.is_empty() {
+        return Err("No login endpoint is set in the settings".into());
+    }
+
+    let url = Url::parse(endpoint)?;
+    let is_local = match url.host_str() {
+        Some(host) if host.eq_ignore_ascii_case("localhost") => true,
+        // IPv6 addresses are in brackets
+        Some(host) => host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse::<IpAddr>()
+            .is_ok_and(|address| address.is_loopback()),
+        None => false,
+    };
+    match url.scheme() {
+        "https" => Ok(url),
+        "http" if is_local => Ok(url),
+        scheme => Err(format!("The login endpoint must use https, not {scheme}").into()),
+    }
+}
+
+/// Sends the login request, returning the response status and body
+fn send_login(
+    settings: &Settings,
+    username: &str,
+    password: &str,
+) -> Result<(StatusCode, Vec<u8>), Box<dyn Error>> {
+    let endpoint = login_endpoint(&settings.login.endpoint)?;
+    let client = transport::build_client(&settings.network)?;
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    let read_timeout = Duration::from_secs(settings.network.read_timeout);
+
+    let body = serde_json::to_vec(&LoginRequest { username, password })?;
+    let request = client
+        .post(endpoint)
+        .header(CONTENT_TYPE, "application/json")
+        .body(body);
+
+    let response = runtime
+        .block_on(async {
+            tokio::time::timeout(read_timeout, async {
+                let response = request.send().await?;
+                let status = response.status();
+                let body = response.bytes().await?;
+                Ok::<_, reqwest::Error>((status, body.to_vec()))
+            })
+            .await
+        })
+        .map_err(|_| "The login server did not answer in time".to_string())??;
+
+    Ok(response)
+}
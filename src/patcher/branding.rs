@@ -0,0 +1,52 @@
+use super::cache::{cache_name, fetch_cached};
+use super::constants::{BRANDING, BRANDING_DIR, CACHE_DIR};
+use super::error::Interruption;
+use super::PatchWorker;
+use crate::branding::{default_links, Branding, BrandingFile};
+
+/// Gets the link buttons and background from the patch server and sends them
+/// to the GUI. If the server can't be reached, or `online` is false, the copy
+/// from last time is used instead. Without either, the GUI keeps its built-in
+/// links and background.
+pub fn update_branding(worker: &PatchWorker, online: bool) -> Result<(), Interruption> {
+    let cache_dir = worker.self_dir.join(CACHE_DIR).join(BRANDING_DIR);
+    let branding_url = worker.branding_url.clone();
+
+    // Links are signed, so nobody can point players at a fake control panel
+    let json_bytes = match fetch_cached(
+        worker,
+        &branding_url,
+        &cache_dir.join(BRANDING),
+        online,
+        true,
+    )? {
+        Some(json_bytes) => json_bytes,
+        None => return Ok(()),
+    };
+
+    let branding_file = match serde_json::from_slice::<BrandingFile>(&json_bytes) {
+        Ok(branding_file) => branding_file,
+        Err(why) => {
+            log::warn!("Could not parse branding: {why}");
+            return Ok(());
+        }
+    };
+
+    let mut links = branding_file.links.unwrap_or_else(default_links);
+    links.retain(|link| link.is_web_link());
+    links.sort_by_key(|link| link.order);
+
+    let mut background = None;
+    if let Some(src) = &branding_file.background {
+        match (branding_url.join(src), cache_name(src)) {
+            (Ok(url), Some(file_name)) => {
+                background = fetch_cached(worker, &url, &cache_dir.join(file_name), online, false)?;
+            }
+            _ => log::warn!("Ignoring background with unusable location {src:?}"),
+        }
+    }
+
+    worker.send_branding(Branding { links, background });
+
+    Ok(())
+}
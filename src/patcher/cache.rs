@@ -0,0 +1,84 @@
+use super::download;
+use super::error::Interruption;
+use super::utils::check_entry_name;
+use super::PatchWorker;
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::path::Path;
+
+/// How much of the end of a location is kept in its cache name, so the cache
+/// can still be looked through by hand
+const MAX_READABLE_NAME: usize = 40;
+
+/// Downloads a file and keeps a copy of it at `cache_path`. If the download
+/// fails, or `online` is false, the copy is returned instead if there is one.
+///
+/// When `signed` is true, downloads which fail signature verification are
+/// treated like failed downloads, so only verified files are ever cached.
+pub fn fetch_cached(
+    worker: &PatchWorker,
+    url: &reqwest::Url,
+    cache_path: &Path,
+    online: bool,
+    signed: bool,
+) -> Result<Option<Vec<u8>>, Interruption> {
+    if online {
+        let result = download::memory_file(worker, url.clone(), |_, _| {}).and_then(|data| {
+            if signed {
+                download::verify_signature(worker, url, &data)?;
+            }
+            Ok(data)
+        });
+
+        match result {
+            Ok(data) => {
+                if let Err(why) = write_cache(cache_path, &data) {
+                    log::warn!("Could not cache {url}: {why}");
+                }
+                return Ok(Some(data));
+            }
+            Err(why) => match why.downcast::<Interruption>() {
+                Ok(interruption) => return Err(*interruption),
+                Err(why) => log::warn!("Could not download {url}: {why}"),
+            },
+        }
+    }
+
+    Ok(std::fs::read(cache_path).ok())
+}
+
+pub fn write_cache(cache_path: &Path, data: &[u8]) -> Result<(), Box<dyn Error>> {
+    if let Some(parent) = cache_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(cache_path, data)?;
+    Ok(())
+}
+
+/// Turns a file location from the patch server into a file name for the
+/// cache, or `None` if no safe name can be made from it. Different locations
+/// which look alike once made safe, such as `a/b.png` and `a_b.png`, still get
+/// different names.
+pub fn cache_name(src: &str) -> Option<String> {
+    let skipped = src.chars().count().saturating_sub(MAX_READABLE_NAME);
+    let readable: String = src
+        .chars()
+        .skip(skipped)
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    let hash: String = Sha256::digest(src.as_bytes())[..8]
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    let name = format!("{hash}-{readable}");
+
+    check_entry_name(&name).ok()?;
+    Some(name)
+}
@@ -1,10 +1,13 @@
 use std::{error::Error, path::Path};
 
-use crate::patcher::utils::set_executable;
+use crate::patcher::utils::{check_entry_name, set_executable};
 use aeco_patch_config::fsobject::Archive;
 use aeco_patch_config::fsobject::{Directory, FSObject, File};
 
 use super::download;
+use super::error::UntrustedUpdate;
+use super::signature;
+use super::worker::UpdatedPatcher;
 use super::PatchWorker;
 
 #[derive(Debug)]
@@ -39,7 +42,7 @@ pub fn check_platform_patches(
     // All files should have been checked, but it is not fatal if these
     // values do not match
     if checked_files != total_files {
-        eprintln!(
+        log::warn!(
             "Checked files: {checked_files}; total files: {total_files}. These should match."
         );
     }
@@ -136,6 +139,9 @@ fn check_file<P>(
 where
     P: AsRef<Path>,
 {
+    // Stop between files if the user wants to pause or cancel
+    worker.check_interruption()?;
+
     let file_to_check = disk_file.as_ref();
     let mut file_to_write = file_to_check.to_path_buf();
 
@@ -154,8 +160,9 @@ where
 
     if !skip_file {
         if !file_to_write.exists() {
-            println!("Downloading new file {net_file} -> {:?}", &file_to_write);
-            let file_bytes = download::patch(worker, net_file)?;
+            log::info!("Downloading new file {net_file} -> {:?}", &file_to_write);
+            let file_bytes = download_patch(worker, net_file, file)?;
+            worker.mark_files_changed();
             std::fs::write(file_to_write, file_bytes)?;
         } else {
             let file_matches = {
@@ -172,8 +179,25 @@ where
             }
 
             if !file_matches {
-                println!("Updating {net_file} -> {:?}", &file_to_write);
-                let file_bytes = download::patch(worker, net_file)?;
+                log::info!("Updating {net_file} -> {:?}", &file_to_write);
+                let file_bytes = download_patch(worker, net_file.clone(), file)?;
+
+                // A replacement for this program will be executed, so it has
+                // to be signed by a trusted key before it is even written.
+                let update_signature = if is_self && signature::CHECK_SIGNATURES {
+                    let signature_file = download::signature_file(worker, &net_file)?;
+                    if let Err(why) = signature::verify(&file_bytes, &signature_file) {
+                        log::error!("Refusing launcher update from {net_file}: {why}");
+                        return Err(UntrustedUpdate(why.to_string()).into());
+                    }
+                    Some(signature_file)
+                } else {
+                    None
+                };
+
+                if !is_self {
+                    worker.mark_files_changed();
+                }
                 std::fs::write(&file_to_write, file_bytes)?;
                 // If we got the file successfully, and it is a replacement for
                 // this program, save the path to the new one for later so we
@@ -181,7 +205,10 @@ where
                 if is_self {
                     // Make sure the file is exectuable on unixlike systems
                     set_executable(&file_to_write)?;
-                    worker.updated_patcher = Some(file_to_write);
+                    worker.updated_patcher = Some(UpdatedPatcher {
+                        path: file_to_write,
+                        signature: update_signature,
+                    });
                 }
             }
         }
@@ -203,6 +230,7 @@ fn check_archive(
     total_files: usize,
 ) -> Result<usize, Box<dyn Error>> {
     // Open the ECO archive
+    log::debug!("Checking archive {archive_paths:?}");
     let mut disk_archive = aeco_archive::Archive::open_pair(archive_paths.dat, archive_paths.hed)?;
 
     // Keep track of if changes were made to this archive.
@@ -212,6 +240,16 @@ fn check_archive(
 
     // Go through each of the files in the patch's archive info
     for file in &archive.files {
+        // Stop between files if the user wants to pause or cancel. Changes
+        // that were already made must be saved so the archive stays usable.
+        if let Err(why) = worker.check_interruption() {
+            if changes_made {
+                log::info!("Saving changes to archive {archive_paths:?} before stopping");
+                disk_archive.finalize()?;
+            }
+            return Err(why.into());
+        }
+
         // Update the GUI to display how many files have been checked so far
         send_checked_files_update(worker, completed_files + 1, total_files, platform);
 
@@ -224,8 +262,9 @@ fn check_archive(
         // into the archive on disk.
         if !file_matches {
             let new_file_url = net_path.join(&file.name)?;
-            println!("Downloading {new_file_url} -> {archive_paths:?}");
-            let new_file_bytes = download::patch(worker, new_file_url)?;
+            log::info!("Downloading {new_file_url} -> {archive_paths:?}");
+            let new_file_bytes = download_patch(worker, new_file_url, file)?;
+            worker.mark_files_changed();
             disk_archive.add_file(&file.name, &new_file_bytes)?;
             changes_made = true;
         }
@@ -236,6 +275,7 @@ fn check_archive(
     // If the archive on disk has been altered, make sure changes get saved,
     // and make sure that any wasted space gets elimintated.
     if changes_made {
+        log::info!("Saving changes to archive {archive_paths:?}");
         disk_archive.finalize()?;
         disk_archive.defrag()?;
     }
@@ -243,6 +283,21 @@ fn check_archive(
     Ok(completed_files)
 }
 
+/// Downloads a patched file, and checks that it matches the digest in the
+/// signed patch info before anything is written
+fn download_patch(
+    worker: &PatchWorker,
+    net_file: reqwest::Url,
+    file: &File,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let data = download::patch(worker, net_file.clone())?;
+    if File::new(&file.name, &data).digest != file.digest {
+        log::error!("{net_file} does not match the patch info");
+        return Err(format!("Downloaded {net_file} does not match the patch info").into());
+    }
+    Ok(data)
+}
+
 /// Reads an ECO archive and checks if a file inside it matches the given File
 /// info.
 fn file_matches_in_archive(
@@ -267,6 +322,46 @@ fn file_matches_in_archive(
     }
 }
 
+/// Makes sure that every file, directory and archive name in the patch info is
+/// safe to use as a path on disk. This needs to happen before anything is
+/// written, since the names come straight from the patch server.
+pub fn validate_patch_names(dir: &Directory) -> Result<(), Box<dyn Error>> {
+    validate_dir_names(dir, &dir.name)
+}
+
+fn validate_dir_names(dir: &Directory, dir_path: &str) -> Result<(), Box<dyn Error>> {
+    for child in &dir.children {
+        let (name, kind) = match child {
+            FSObject::File(f) => (&f.name, "file"),
+            FSObject::Directory(d) => (&d.name, "directory"),
+            FSObject::Archive(a) => (&a.name, "archive"),
+        };
+
+        let entry_path = format!("{dir_path}/{name}");
+        check_entry_name(name)
+            .map_err(|reason| format!("Refusing {kind} {entry_path:?} in patch info: {reason}"))?;
+
+        match child {
+            FSObject::Directory(d) => validate_dir_names(d, &entry_path)?,
+            // Files in an archive are downloaded from next to the archive on
+            // the patch server, so their names end up in URLs
+            FSObject::Archive(a) => {
+                for file in &a.files {
+                    check_entry_name(&file.name).map_err(|reason| {
+                        format!(
+                            "Refusing file {:?} in archive {entry_path:?} in patch info: {reason}",
+                            file.name
+                        )
+                    })?;
+                }
+            }
+            FSObject::File(_) => {}
+        }
+    }
+
+    Ok(())
+}
+
 fn get_total_files_in_patch(dir: &Directory) -> usize {
     let mut total_files = 0;
 
@@ -293,3 +388,15 @@ fn send_checked_files_update(
         progress,
     );
 }
+
+/// Gets a Directory child from a Directory by name, if it is present
+pub fn subdir_by_name<'a>(dir: &'a Directory, name: &str) -> Option<&'a Directory> {
+    for child in &dir.children {
+        if let FSObject::Directory(d) = child {
+            if d.name == name {
+                return Some(d);
+            }
+        }
+    }
+    None
+}
@@ -1,9 +1,41 @@
 pub const PATCH_SERVER: &str = "https://ecocp.atomixro.com/ecopatch/saga10/";
 pub const BASE_DIR: &str = "base/";
 pub const BASE_ZIP: &str = "saga10.zip";
+/// Extension given to the base game ZIP while it is still being downloaded
+pub const PARTIAL_EXTENSION: &str = "part";
+/// Added to the name of a partial download for the file which says which
+/// version of the file on the server it is part of
+pub const VALIDATOR_EXTENSION: &str = "validator";
+/// Present in the game directory while the base game is being extracted
+pub const INSTALL_MARKER: &str = ".aeco-installing";
 pub const META_DIR: &str = "meta/";
 pub const PATCHLIST: &str = "patchlist.json";
 pub const STATUS: &str = "status.json";
+pub const NEWS: &str = "news.json";
+pub const BRANDING: &str = "branding.json";
+/// Files from the patch server which are kept for offline use are stored here
+pub const CACHE_DIR: &str = "launcher-cache";
+pub const NEWS_IMAGE_DIR: &str = "news";
+pub const BRANDING_DIR: &str = "branding";
+/// Extension of the detached signature stored next to a signed file
+pub const SIGNATURE_EXTENSION: &str = "sig";
+/// Extension of the signed file holding the SHA-256 of the base game ZIP,
+/// which is too large to be signed directly
+pub const DIGEST_EXTENSION: &str = "sha256";
 pub const PATCH_DIR: &str = "patch/";
 pub const GAME_EXE: &str = "eco.exe";
 pub const GAME_INI: &str = "eco.ini";
+/// Saved copies of eco.ini which the game and its clients can be started with
+pub const INI_PROFILE_DIR: &str = "ini-profiles";
+/// Copies of each version of eco.ini the launcher has seen
+pub const INI_BACKUP_DIR: &str = "ini-backups";
+/// How many backups of eco.ini are kept before the oldest are removed
+pub const MAX_INI_BACKUPS: usize = 30;
+/// Logs written by the launcher, inside the game directory
+pub const LOG_DIR: &str = "launcher-logs";
+/// Output of the game and Wine, replaced every time the game starts
+pub const GAME_LOG: &str = "game.log";
+/// Wine prefix for the game on Unixlike systems, inside the game directory
+pub const WINE_PREFIX_DIR: &str = "wine-prefix";
+/// Present in a Wine prefix once it is set up, holding the setup version
+pub const WINE_PREFIX_MARKER: &str = ".aeco-prefix";
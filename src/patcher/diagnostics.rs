@@ -0,0 +1,503 @@
+use super::constants::GAME_EXE;
+#[cfg(unix)]
+use super::wine;
+use super::PatchWorker;
+use crate::message::{Check, CheckStatus};
+#[cfg(unix)]
+use crate::runner;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+/// Name of the file used to test whether the game directory is writable
+const WRITE_TEST_FILE: &str = ".aeco-write-test";
+
+/// Checks everything the game needs to start, so problems can be explained
+/// to the player instead of the game silently failing to start
+pub fn run_checks(worker: &PatchWorker) -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    #[cfg(unix)]
+    {
+        checks.push(check_runner(worker));
+        checks.push(check_prefix(worker));
+        checks.push(check_fonts());
+    }
+    checks.push(check_game_exe(worker));
+    checks.push(check_dlls(worker));
+    checks.push(check_write_permission(worker));
+
+    checks
+}
+
+fn check(name: &str, status: CheckStatus, detail: String, remedy: Option<String>) -> Check {
+    Check {
+        name: name.to_string(),
+        status,
+        detail,
+        remedy,
+    }
+}
+
+#[cfg(unix)]
+fn check_runner(worker: &PatchWorker) -> Check {
+    use std::os::unix::fs::PermissionsExt;
+
+    let name = "Wine";
+    let runner = match runner::selected(&worker.runners, &worker.settings.wine) {
+        Some(runner) => runner,
+        None => {
+            return check(
+                name,
+                CheckStatus::Failed,
+                "Wine is needed to run the game, but it was not found.".to_string(),
+                Some(install_wine_remedy()),
+            )
+        }
+    };
+
+    let is_executable = std::fs::metadata(&runner.wine)
+        .map(|metadata| metadata.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false);
+    if !is_executable {
+        return check(
+            name,
+            CheckStatus::Failed,
+            format!("{} is not executable.", runner.wine.display()),
+            Some(format!("Run: chmod +x \"{}\"", runner.wine.display())),
+        );
+    }
+
+    match &runner.version {
+        Some(_) => check(name, CheckStatus::Passed, runner.label(), None),
+        None => check(
+            name,
+            CheckStatus::Failed,
+            format!("{} did not report its version.", runner.wine.display()),
+            Some("The runner may be broken. Pick another runner in Settings.".to_string()),
+        ),
+    }
+}
+
+/// Explains how to install Wine on the player's distribution
+#[cfg(unix)]
+fn install_wine_remedy() -> String {
+    let os_release = std::fs::read_to_string("/etc/os-release").unwrap_or_default();
+    let ids: Vec<&str> = os_release
+        .lines()
+        .filter_map(|line| {
+            line.strip_prefix("ID=")
+                .or_else(|| line.strip_prefix("ID_LIKE="))
+        })
+        .flat_map(|value| value.trim_matches('"').split_whitespace())
+        .collect();
+
+    let command = [
+        ("debian", "sudo apt install wine"),
+        ("ubuntu", "sudo apt install wine"),
+        ("fedora", "sudo dnf install wine"),
+        ("arch", "sudo pacman -S wine"),
+        ("suse", "sudo zypper install wine"),
+        ("opensuse", "sudo zypper install wine"),
+    ]
+    .into_iter()
+    .find(|(id, _)| ids.contains(id))
+    .map(|(_, command)| command);
+
+    match command {
+        Some(command) => {
+            format!("Install Wine with \"{command}\", or pick a runner such as Proton in Settings.")
+        }
+        None => "Install Wine using your system's package manager, or pick a runner such as \
+                 Proton in Settings."
+            .to_string(),
+    }
+}
+
+#[cfg(unix)]
+fn check_prefix(worker: &PatchWorker) -> Check {
+    let name = "Wine prefix";
+    if wine::is_prefix_ready(&worker.self_dir) {
+        check(
+            name,
+            CheckStatus::Passed,
+            format!("{}", wine::prefix_path(&worker.self_dir).display()),
+            None,
+        )
+    } else {
+        check(
+            name,
+            CheckStatus::Warning,
+            "The Wine prefix has not been set up yet.".to_string(),
+            Some("It is set up when the game starts, which can take a minute.".to_string()),
+        )
+    }
+}
+
+#[cfg(unix)]
+fn check_fonts() -> Check {
+    let name = "Japanese fonts";
+    match wine::find_japanese_font() {
+        Some(font) => check(name, CheckStatus::Passed, font, None),
+        None => check(
+            name,
+            CheckStatus::Warning,
+            "No font which can display Japanese was found.".to_string(),
+            Some(
+                "Some text in the game may not display. Install a font such as Noto Sans CJK JP, \
+                 then reset the Wine prefix in Settings."
+                    .to_string(),
+            ),
+        ),
+    }
+}
+
+fn check_game_exe(worker: &PatchWorker) -> Check {
+    let name = "Game files";
+    let game_exe = worker.self_dir.join(GAME_EXE);
+    if game_exe.is_file() {
+        check(
+            name,
+            CheckStatus::Passed,
+            format!("{GAME_EXE} was found."),
+            None,
+        )
+    } else {
+        check(
+            name,
+            CheckStatus::Failed,
+            format!("{GAME_EXE} is missing from {}.", worker.self_dir.display()),
+            Some(
+                "Press RETRY to download the game again. If it keeps disappearing, your \
+                 antivirus may be removing it."
+                    .to_string(),
+            ),
+        )
+    }
+}
+
+/// Checks that every DLL the game imports can be found. This only looks in
+/// the usual places, so a DLL which isn't found is only a warning.
+fn check_dlls(worker: &PatchWorker) -> Check {
+    let name = "DLLs";
+    let game_exe = worker.self_dir.join(GAME_EXE);
+    if !game_exe.is_file() {
+        return check(
+            name,
+            CheckStatus::Warning,
+            "Skipped because the game is missing.".to_string(),
+            None,
+        );
+    }
+
+    let dlls = match imported_dlls(&game_exe) {
+        Ok(dlls) => dlls,
+        Err(why) => {
+            return check(
+                name,
+                CheckStatus::Warning,
+                format!("{GAME_EXE} could not be read: {why}"),
+                None,
+            )
+        }
+    };
+
+    let search_dirs = match dll_search_dirs(worker) {
+        Some(search_dirs) => search_dirs,
+        None => {
+            return check(
+                name,
+                CheckStatus::Warning,
+                "Skipped until the Wine prefix is set up.".to_string(),
+                None,
+            )
+        }
+    };
+
+    // API sets are provided by Windows itself and don't exist as files
+    let missing: Vec<String> = dlls
+        .into_iter()
+        .filter(|dll| {
+            let dll = dll.to_ascii_lowercase();
+            !dll.starts_with("api-ms-") && !dll.starts_with("ext-ms-")
+        })
+        .filter(|dll| !search_dirs.iter().any(|dir| contains_file(dir, dll)))
+        .collect();
+
+    if missing.is_empty() {
+        check(
+            name,
+            CheckStatus::Passed,
+            "Every DLL the game needs was found.".to_string(),
+            None,
+        )
+    } else {
+        let remedy = if cfg!(unix) {
+            "Press RETRY to repair the game files. If the DLLs are not part of the game, install \
+             them into the Wine prefix with winetricks."
+        } else {
+            "Press RETRY to repair the game files. If the DLLs are not part of the game, install \
+             the DirectX End-User Runtime and the Visual C++ Redistributable."
+        };
+        // Windows can also load DLLs from places which aren't searched here,
+        // such as the side-by-side store, so this doesn't stop the game from
+        // starting
+        check(
+            name,
+            CheckStatus::Warning,
+            format!("Not found: {}", missing.join(", ")),
+            Some(remedy.to_string()),
+        )
+    }
+}
+
+/// Where Windows looks for the game's DLLs, or `None` if they can't be
+/// checked yet
+fn dll_search_dirs(worker: &PatchWorker) -> Option<Vec<PathBuf>> {
+    let mut dirs = vec![worker.self_dir.clone()];
+
+    #[cfg(unix)]
+    {
+        if !wine::is_prefix_ready(&worker.self_dir) {
+            return None;
+        }
+        let windows_dir = wine::prefix_path(&worker.self_dir)
+            .join("drive_c")
+            .join("windows");
+        dirs.push(windows_dir.join("syswow64"));
+        dirs.push(windows_dir.join("system32"));
+    }
+
+    #[cfg(windows)]
+    {
+        let windows_dir = PathBuf::from(std::env::var_os("SystemRoot")?);
+        dirs.push(windows_dir.join("SysWOW64"));
+        dirs.push(windows_dir.join("System32"));
+    }
+
+    Some(dirs)
+}
+
+/// Windows file names are not case sensitive
+fn contains_file(dir: &Path, file_name: &str) -> bool {
+    match std::fs::read_dir(dir) {
+        Ok(entries) => entries.filter_map(Result::ok).any(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .eq_ignore_ascii_case(file_name)
+        }),
+        Err(_) => false,
+    }
+}
+
+fn check_write_permission(worker: &PatchWorker) -> Check {
+    let name = "Write permission";
+    let test_file = worker.self_dir.join(WRITE_TEST_FILE);
+    let result = std::fs::write(&test_file, b"").and_then(|_| std::fs::remove_file(&test_file));
+
+    match result {
+        Ok(_) => check(
+            name,
+            CheckStatus::Passed,
+            format!("{} is writable.", worker.self_dir.display()),
+            None,
+        ),
+        Err(why) => check(
+            name,
+            CheckStatus::Failed,
+            format!("{} is not writable: {why}", worker.self_dir.display()),
+            Some(
+                "The launcher needs to update the game. Move the game to a folder you own, such \
+                 as your home or Documents folder."
+                    .to_string(),
+            ),
+        ),
+    }
+}
+
+/// Lists the DLLs imported by a Windows executable
+fn imported_dlls(path: &Path) -> Result<Vec<String>, Box<dyn Error>> {
+    parse_imported_dlls(&std::fs::read(path)?)
+}
+
+/// Lists the DLLs imported by the Windows executable in `data`. Every offset
+/// comes from the file itself, so none of them are trusted.
+fn parse_imported_dlls(data: &[u8]) -> Result<Vec<String>, Box<dyn Error>> {
+    let bytes_at = |offset: usize, len: usize| -> Result<&[u8], Box<dyn Error>> {
+        offset
+            .checked_add(len)
+            .and_then(|end| data.get(offset..end))
+            .ok_or_else(|| "Unexpected end of file".into())
+    };
+    let u16_at = |offset: usize| -> Result<usize, Box<dyn Error>> {
+        let bytes = bytes_at(offset, 2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]) as usize)
+    };
+    let u32_at = |offset: usize| -> Result<usize, Box<dyn Error>> {
+        let bytes = bytes_at(offset, 4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+    };
+    let add = |a: usize, b: usize| -> Result<usize, Box<dyn Error>> {
+        a.checked_add(b)
+            .ok_or_else(|| "Offset is out of range".into())
+    };
+
+    let pe_offset = u32_at(0x3C)?;
+    if bytes_at(pe_offset, 4).ok() != Some(b"PE\0\0") {
+        return Err("Not a Windows executable".into());
+    }
+
+    let section_count = u16_at(add(pe_offset, 6)?)?;
+    let optional_header = add(pe_offset, 24)?;
+    let optional_header_size = u16_at(add(pe_offset, 20)?)?;
+    let data_directories = match u16_at(optional_header)? {
+        0x10B => add(optional_header, 96)?,
+        0x20B => add(optional_header, 112)?,
+        _ => return Err("Unknown executable format".into()),
+    };
+    let import_rva = u32_at(add(data_directories, 8)?)?;
+    if import_rva == 0 {
+        return Ok(Vec::new());
+    }
+
+    // Addresses in the headers are for the loaded program, so they have to
+    // be mapped back to the file through the section table
+    let sections = add(optional_header, optional_header_size)?;
+    let rva_to_offset = |rva: usize| -> Result<usize, Box<dyn Error>> {
+        for i in 0..section_count {
+            let section = add(sections, i * 40)?;
+            let virtual_size = u32_at(add(section, 8)?)?;
+            let virtual_address = u32_at(add(section, 12)?)?;
+            let raw_size = u32_at(add(section, 16)?)?;
+            let raw_offset = u32_at(add(section, 20)?)?;
+            let end = add(virtual_address, virtual_size.max(raw_size))?;
+            if rva >= virtual_address && rva < end {
+                return add(rva - virtual_address, raw_offset);
+            }
+        }
+        Err("Address is outside of every section".into())
+    };
+
+    let mut dlls = Vec::new();
+    let mut descriptor = rva_to_offset(import_rva)?;
+    loop {
+        let name_rva = u32_at(add(descriptor, 12)?)?;
+        if name_rva == 0 {
+            break;
+        }
+
+        let name_offset = rva_to_offset(name_rva)?;
+        let name_bytes = data.get(name_offset..).ok_or("Unexpected end of file")?;
+        let name_len = name_bytes
+            .iter()
+            .position(|&byte| byte == 0)
+            .ok_or("Unterminated DLL name")?;
+        dlls.push(String::from_utf8_lossy(&name_bytes[..name_len]).to_string());
+
+        descriptor = add(descriptor, 20)?;
+    }
+
+    Ok(dlls)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_imported_dlls;
+
+    /// Where the only section is in the file, and where it is loaded
+    const SECTION_OFFSET: usize = 0x200;
+    const SECTION_ADDRESS: usize = 0x1000;
+
+    fn put_u16(data: &mut [u8], offset: usize, value: u16) {
+        data[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn put_u32(data: &mut [u8], offset: usize, value: u32) {
+        data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    /// Builds the smallest 32-bit executable the parser understands, with one
+    /// section holding the import table and the names of `dlls`
+    fn minimal_pe(dlls: &[&str]) -> Vec<u8> {
+        let mut data = vec![0u8; 0x400];
+        let pe_offset = 0x40;
+        put_u32(&mut data, 0x3C, pe_offset as u32);
+        data[pe_offset..pe_offset + 4].copy_from_slice(b"PE\0\0");
+        // One section, after a 32-bit optional header
+        put_u16(&mut data, pe_offset + 6, 1);
+        put_u16(&mut data, pe_offset + 20, 0xE0);
+        let optional_header = pe_offset + 24;
+        put_u16(&mut data, optional_header, 0x10B);
+        // The import table is at the start of the section
+        put_u32(&mut data, optional_header + 96 + 8, SECTION_ADDRESS as u32);
+
+        let section = optional_header + 0xE0;
+        put_u32(&mut data, section + 8, 0x200);
+        put_u32(&mut data, section + 12, SECTION_ADDRESS as u32);
+        put_u32(&mut data, section + 16, 0x200);
+        put_u32(&mut data, section + 20, SECTION_OFFSET as u32);
+
+        // One import descriptor per DLL, then an empty one. The names come
+        // after the descriptors.
+        let mut name_offset = SECTION_OFFSET + 0x100;
+        for (i, dll) in dlls.iter().enumerate() {
+            let descriptor = SECTION_OFFSET + i * 20;
+            let name_address = name_offset - SECTION_OFFSET + SECTION_ADDRESS;
+            put_u32(&mut data, descriptor + 12, name_address as u32);
+            data[name_offset..name_offset + dll.len()].copy_from_slice(dll.as_bytes());
+            name_offset += dll.len() + 1;
+        }
+        data
+    }
+
+    #[test]
+    fn lists_imported_dlls() {
+        let data = minimal_pe(&["KERNEL32.dll", "d3d9.dll"]);
+        assert_eq!(
+            parse_imported_dlls(&data).unwrap(),
+            ["KERNEL32.dll", "d3d9.dll"]
+        );
+    }
+
+    #[test]
+    fn no_imports() {
+        let mut data = minimal_pe(&[]);
+        put_u32(&mut data, 0x40 + 24 + 96 + 8, 0);
+        assert!(parse_imported_dlls(&data).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        let data = minimal_pe(&["KERNEL32.dll"]);
+        // Cut off in the headers, in the section table, in the import table
+        // and in the middle of the name
+        for len in [
+            0,
+            0x3E,
+            0x50,
+            0x140,
+            SECTION_OFFSET + 10,
+            SECTION_OFFSET + 0x104,
+        ] {
+            assert!(parse_imported_dlls(&data[..len]).is_err(), "{len:#x}");
+        }
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert!(parse_imported_dlls(b"MZ not really an executable at all").is_err());
+        assert!(parse_imported_dlls(&[0u8; 0x100]).is_err());
+    }
+
+    #[test]
+    fn rejects_offsets_outside_the_file() {
+        let mut data = minimal_pe(&["KERNEL32.dll"]);
+        put_u32(&mut data, 0x3C, u32::MAX);
+        assert!(parse_imported_dlls(&data).is_err());
+
+        let mut data = minimal_pe(&["KERNEL32.dll"]);
+        // The name is somewhere no section is loaded
+        put_u32(&mut data, SECTION_OFFSET + 12, u32::MAX);
+        assert!(parse_imported_dlls(&data).is_err());
+    }
+}
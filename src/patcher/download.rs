@@ -1,57 +1,99 @@
 use std::error::Error;
 use std::fs::File;
-use std::io::Write;
+use std::io::{Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
 
-use super::error::{PatchError, ToPatchError};
+use super::cache;
+use super::constants::{
+    BASE_ZIP, CACHE_DIR, DIGEST_EXTENSION, PARTIAL_EXTENSION, PATCHLIST, SIGNATURE_EXTENSION,
+    VALIDATOR_EXTENSION,
+};
+use super::error::{CorruptArchive, ErrorCode, PatchError, ToPatchError};
+use super::signature;
+use super::status::StatusDocument;
 use super::utils::byte_string;
 use super::PatchWorker;
 use aeco_patch_config::fsobject::Directory;
-use aeco_patch_config::status::ServerStatus;
-use futures_util::StreamExt;
+use sha2::{Digest, Sha256};
 
-pub fn server_status(worker: &PatchWorker) -> Result<ServerStatus, PatchError> {
-    let json_bytes = memory_file(worker, worker.status_url.clone(), |_, _| {})
-        .map_err(|why| why.to_patch_error("Failed to get server status"))?;
+pub fn server_status(worker: &PatchWorker) -> Result<StatusDocument, PatchError> {
+    let json_bytes = memory_file(worker, worker.status_url.clone(), |_, _| {}).map_err(|why| {
+        why.to_patch_error("Failed to get server status", ErrorCode::ServerUnreachable)
+    })?;
 
-    let server_status = serde_json::from_slice::<ServerStatus>(&json_bytes)
-        .map_err(|why| why.to_patch_error("Failed to parse server status"))?;
+    verify_signature(worker, &worker.status_url, &json_bytes).map_err(|why| {
+        why.to_patch_error(
+            "Server status failed verification",
+            ErrorCode::ServerStatusInvalid,
+        )
+    })?;
+
+    let server_status = StatusDocument::from_slice(&json_bytes).map_err(|why| {
+        why.to_patch_error(
+            "Failed to parse server status",
+            ErrorCode::ServerStatusInvalid,
+        )
+    })?;
 
     Ok(server_status)
 }
 
-/// Downloads a file and returns it in a temporary file
-pub fn temp_file<F>(
+/// Downloads a file to `path`, continuing from where an earlier download of
+/// the same file stopped. The file is left on disk if the download is
+/// interrupted so that it can be resumed later.
+pub fn resumable_file<F>(
     worker: &PatchWorker,
     url: reqwest::Url,
+    path: &Path,
     callback: F,
 ) -> Result<std::fs::File, Box<dyn Error>>
 where
     F: Fn(u64, Option<u64>), /* downloaded bytes, total bytes */
 {
-    // Request URL
-    let response = worker
-        .runtime
-        .block_on(worker.client.get(url).send())
-        .map_err(|why| why.to_string())?;
+    // Find out how much was downloaded previously, and of which version of
+    // the file
+    let existing_size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    let validator_path = validator_path(path);
+    let validator = std::fs::read_to_string(&validator_path).ok();
+    log::info!("Downloading {url} to {path:?}");
 
-    // Check response status
-    let status = response.status();
-    if !status.is_success() {
-        Err(format!("URL request failed: {status}"))?;
-    }
+    // Only ask for the part of the file that is still missing
+    let mut download = worker
+        .transport
+        .fetch(&url, existing_size, validator.as_deref())?;
 
-    // Create a new temporary file for the data to go into
-    let mut file = tempfile::tempfile_in(&worker.self_dir).map_err(|why| why.to_string())?;
+    // The transport may not be able to skip ahead, or the file may have
+    // changed, in which case the whole file is sent again and the download
+    // starts over
+    let resuming = existing_size > 0 && download.resumed;
+    if resuming {
+        log::info!("Resuming after {}", byte_string(existing_size));
+    } else {
+        // Remember which version of the file this is, for resuming later
+        match &download.validator {
+            Some(validator) => std::fs::write(&validator_path, validator)?,
+            None => remove_if_present(&validator_path)?,
+        }
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .read(true)
+        .append(resuming)
+        .write(!resuming)
+        .truncate(!resuming)
+        .open(path)
+        .map_err(|why| why.to_string())?;
 
     // Keep track of the total size and the number of bytes downloaded so far.
     // The server doesn't need to tell us how long the content is.
-    let total_size = response.content_length();
-    let mut downloaded_size = 0u64;
+    let mut downloaded_size = if resuming { existing_size } else { 0 };
+    let total_size = download
+        .remaining_size
+        .map(|remaining| remaining + downloaded_size);
 
-    let mut stream = response.bytes_stream();
-    while let Some(stream_result) = worker.runtime.block_on(stream.next()) {
-        // Get next chunk of bytes from stream
-        let bytes = stream_result?;
+    while let Some(bytes) = download.body.next_chunk()? {
+        // Stop between chunks if the user wants to pause or cancel
+        worker.check_interruption()?;
 
         // Write the bytes to the file
         file.write_all(&bytes).map_err(|why| why.to_string())?;
@@ -61,6 +103,7 @@ where
         callback(downloaded_size, total_size);
     }
 
+    log::info!("Downloaded {url} ({})", byte_string(downloaded_size));
     Ok(file)
 }
 
@@ -74,17 +117,12 @@ where
     F: Fn(u64, Option<u64>), /* downloaded bytes, total bytes */
 {
     // Request URL
-    let response = worker.runtime.block_on(worker.client.get(url).send())?;
-
-    // Check response status
-    let status = response.status();
-    if !status.is_success() {
-        return Err(format!("URL request failed: {status}").into());
-    }
+    log::debug!("Downloading {url}");
+    let mut download = worker.transport.fetch(&url, 0, None)?;
 
     // Keep track of the total size and the number of bytes downloaded so far.
     // The server doesn't need to tell us how long the content is.
-    let total_size = response.content_length();
+    let total_size = download.remaining_size;
     let mut downloaded_size = 0u64;
 
     // If we know the total size of the download, we can pre-allocate the Vec
@@ -98,10 +136,9 @@ where
         None => Vec::<u8>::new(),
     };
 
-    let mut stream = response.bytes_stream();
-    while let Some(stream_result) = worker.runtime.block_on(stream.next()) {
-        // Get next chunk of bytes from stream
-        let bytes = stream_result.map_err(|why| why.to_string())?;
+    while let Some(bytes) = download.body.next_chunk()? {
+        // Stop between chunks if the user wants to pause or cancel
+        worker.check_interruption()?;
 
         // Write the bytes to the Vec
         result.extend(&bytes);
@@ -111,17 +148,81 @@ where
         callback(downloaded_size, total_size);
     }
 
+    log::debug!("Downloaded {url} ({})", byte_string(downloaded_size));
     Ok(result)
 }
 
+/// Downloads the detached signature of the file at `url`
+pub fn signature_file(worker: &PatchWorker, url: &reqwest::Url) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut signature_url = url.clone();
+    signature_url.set_path(&format!("{}.{SIGNATURE_EXTENSION}", url.path()));
+    memory_file(worker, signature_url, |_, _| {})
+}
+
+/// Checks that `data`, which was downloaded from `url`, is signed by a key
+/// that this launcher trusts, with a signature which hasn't expired
+pub fn verify_signature(
+    worker: &PatchWorker,
+    url: &reqwest::Url,
+    data: &[u8],
+) -> Result<(), Box<dyn Error>> {
+    if !signature::CHECK_SIGNATURES {
+        return Ok(());
+    }
+
+    let signature_file = signature_file(worker, url)?;
+    signature::verify_document(data, &signature_file)
+}
+
 /// Downloads a file and returns the resulting bytes
 pub fn patch(worker: &PatchWorker, net_file: reqwest::Url) -> Result<Vec<u8>, Box<dyn Error>> {
     let data = memory_file(worker, net_file, |_, _| {})?;
     Ok(data)
 }
 
+/// Where the base game archive is kept while it is being downloaded
+pub fn game_base_path(worker: &PatchWorker) -> PathBuf {
+    worker
+        .self_dir
+        .join(format!("{BASE_ZIP}.{PARTIAL_EXTENSION}"))
+}
+
+/// Removes the base game archive, so that it is downloaded from the start
+/// next time
+pub fn remove_game_base(worker: &PatchWorker) -> std::io::Result<()> {
+    let path = game_base_path(worker);
+    remove_if_present(&validator_path(&path))?;
+    remove_if_present(&path)
+}
+
+/// Where the validator of a partial download is kept
+fn validator_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{VALIDATOR_EXTENSION}"));
+    PathBuf::from(name)
+}
+
+fn remove_if_present(path: &Path) -> std::io::Result<()> {
+    match std::fs::remove_file(path) {
+        Err(why) if why.kind() != std::io::ErrorKind::NotFound => Err(why),
+        _ => Ok(()),
+    }
+}
+
+/// Downloads the base game archive. Its SHA-256 is signed, and the archive
+/// is only returned if it matches. An archive which doesn't is reported as a
+/// `CorruptArchive`.
 pub fn game_base(worker: &PatchWorker) -> Result<File, Box<dyn Error>> {
-    temp_file(worker, worker.game_zip_url.clone(), |downloaded, total| {
+    let path = game_base_path(worker);
+    let url = worker.game_zip_url.clone();
+    // Fetched first, so that a missing digest fails before the long download
+    let digest = if signature::CHECK_SIGNATURES {
+        Some(game_base_digest(worker, &url)?)
+    } else {
+        None
+    };
+
+    let mut file = resumable_file(worker, url, &path, |downloaded, total| {
         let pretty_downloaded = byte_string(downloaded);
         if let Some(total) = total {
             let downloaded = downloaded.min(total);
@@ -134,7 +235,47 @@ pub fn game_base(worker: &PatchWorker) -> Result<File, Box<dyn Error>> {
         } else {
             worker.send_download(format!("Downloading base game ({pretty_downloaded})"), 1.);
         }
-    })
+    })?;
+
+    if let Some(digest) = digest {
+        worker.send_download("Verifying base game".to_string(), 1.);
+        file.seek(SeekFrom::Start(0))?;
+        let mut hasher = Sha256::new();
+        std::io::copy(&mut file, &mut hasher)?;
+        let actual: String = hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+        if actual != digest {
+            return Err(CorruptArchive(format!(
+                "its SHA-256 is {actual}, but the signed one is {digest}"
+            ))
+            .into());
+        }
+    }
+    Ok(file)
+}
+
+/// Downloads the signed SHA-256 of the file at `url`, as lowercase hex. The
+/// digest file holds the hex digest, optionally followed by the file name as
+/// written by `sha256sum`.
+fn game_base_digest(worker: &PatchWorker, url: &reqwest::Url) -> Result<String, Box<dyn Error>> {
+    let mut digest_url = url.clone();
+    digest_url.set_path(&format!("{}.{DIGEST_EXTENSION}", url.path()));
+    let data = memory_file(worker, digest_url.clone(), |_, _| {})?;
+    // The base game doesn't change, so its digest doesn't need to expire
+    signature::verify(&data, &signature_file(worker, &digest_url)?)?;
+
+    let digest = String::from_utf8_lossy(&data)
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    if digest.len() != 64 || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("{digest_url} does not hold a SHA-256").into());
+    }
+    Ok(digest)
 }
 
 /// Downloads the patchlist and returns the parsed result
@@ -154,10 +295,26 @@ pub fn patch_metadata(worker: &PatchWorker) -> Result<Directory, PatchError> {
         }
     });
 
-    let json_bytes = result.map_err(|why| why.to_patch_error("Failed to get patch info"))?;
+    let json_bytes = result.map_err(|why| {
+        why.to_patch_error("Failed to get patch info", ErrorCode::PatchInfoUnreachable)
+    })?;
+
+    verify_signature(worker, &worker.patchlist_url, &json_bytes).map_err(|why| {
+        why.to_patch_error(
+            "Patch info failed verification",
+            ErrorCode::PatchInfoInvalid,
+        )
+    })?;
 
-    let patch_dir = serde_json::from_slice::<Directory>(&json_bytes)
-        .map_err(|why| why.to_patch_error("Failed to parse patch info"))?;
+    let patch_dir = serde_json::from_slice::<Directory>(&json_bytes).map_err(|why| {
+        why.to_patch_error("Failed to parse patch info", ErrorCode::PatchInfoInvalid)
+    })?;
+
+    // Kept so local files can be checked against it for diagnostics
+    let cache_path = worker.self_dir.join(CACHE_DIR).join(PATCHLIST);
+    if let Err(why) = cache::write_cache(&cache_path, &json_bytes) {
+        log::warn!("Could not cache patch info: {why}");
+    }
 
     Ok(patch_dir)
 }
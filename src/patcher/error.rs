@@ -5,10 +5,122 @@ pub struct PatchError {
     pub internal_error: Box<dyn Error>,
     /// The message which will be displayed in the client
     pub friendly_message: String,
+    /// What kind of failure this is, for support
+    pub code: ErrorCode,
     /// Controls the color of the error in the GUI
     pub level: PatchErrorLevel,
 }
 
+/// Kinds of failures, identified by codes which players can quote to support.
+/// A code must never change meaning or be reused once it has been released.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ErrorCode {
+    /// The server status could not be downloaded
+    ServerUnreachable,
+    /// The server status was downloaded, but could not be trusted or read
+    ServerStatusInvalid,
+    /// The patch info could not be downloaded
+    PatchInfoUnreachable,
+    /// The patch info was downloaded, but could not be trusted or used
+    PatchInfoInvalid,
+    Maintenance,
+    News,
+    Branding,
+    LauncherOutdated,
+    /// A launcher update was not signed by a trusted key
+    UntrustedUpdate,
+    /// A launcher update could not be installed or started
+    LauncherUpdate,
+    BaseGameDownload,
+    BaseGameExtract,
+    /// Game files could not be checked or patched
+    PatchFiles,
+    GameLaunch,
+    /// eco.ini or its profiles and backups could not be changed
+    GameIni,
+    Settings,
+    Diagnostics,
+    WinePrefix,
+}
+
+impl ErrorCode {
+    /// The code shown to the player, such as `E101`
+    pub fn code(&self) -> &'static str {
+        match self {
+            ErrorCode::ServerUnreachable => "E101",
+            ErrorCode::ServerStatusInvalid => "E102",
+            ErrorCode::PatchInfoUnreachable => "E103",
+            ErrorCode::PatchInfoInvalid => "E104",
+            ErrorCode::Maintenance => "E105",
+            ErrorCode::News => "E106",
+            ErrorCode::Branding => "E107",
+            ErrorCode::LauncherOutdated => "E201",
+            ErrorCode::UntrustedUpdate => "E202",
+            ErrorCode::LauncherUpdate => "E203",
+            ErrorCode::BaseGameDownload => "E301",
+            ErrorCode::BaseGameExtract => "E302",
+            ErrorCode::PatchFiles => "E303",
+            ErrorCode::GameLaunch => "E401",
+            ErrorCode::GameIni => "E402",
+            ErrorCode::Settings => "E501",
+            ErrorCode::Diagnostics => "E502",
+            ErrorCode::WinePrefix => "E503",
+        }
+    }
+
+    /// What the player can try to fix the problem
+    pub fn remedy(&self) -> &'static str {
+        match self {
+            ErrorCode::ServerUnreachable | ErrorCode::PatchInfoUnreachable => {
+                "Check your internet connection and the proxy settings, then press RETRY. \
+                 If other websites work, the patch server may be down for a while."
+            }
+            ErrorCode::ServerStatusInvalid | ErrorCode::PatchInfoInvalid => {
+                "Something between you and the patch server changed what was downloaded, \
+                 or the server is misconfigured. Press RETRY later, and contact support if \
+                 this keeps happening."
+            }
+            ErrorCode::Maintenance => "Wait for maintenance to end, then press RETRY.",
+            ErrorCode::News | ErrorCode::Branding => "Press RETRY.",
+            ErrorCode::LauncherOutdated => {
+                "Download the latest launcher from the website and replace this one."
+            }
+            ErrorCode::UntrustedUpdate => {
+                "Do not run the update. Download the latest launcher from the website \
+                 instead, and tell support about this."
+            }
+            ErrorCode::LauncherUpdate => {
+                "Make sure the game directory can be written to and that no other copy of \
+                 the launcher is running, then start the launcher again."
+            }
+            ErrorCode::BaseGameDownload => {
+                "Check your internet connection and free disk space, then press RETRY. \
+                 The download continues where it stopped."
+            }
+            ErrorCode::BaseGameExtract | ErrorCode::PatchFiles => {
+                "Make sure there is enough free disk space, the game directory can be \
+                 written to and the game is not running, then press RETRY. Antivirus \
+                 programs can also block changes to game files."
+            }
+            ErrorCode::GameLaunch => {
+                "Open Settings and press Check setup to find out what is missing."
+            }
+            ErrorCode::GameIni => {
+                "Close the game and make sure eco.ini can be written to, then try again."
+            }
+            ErrorCode::Settings => {
+                "Make sure the game directory can be written to, then save the settings again."
+            }
+            ErrorCode::Diagnostics => {
+                "Make sure your Downloads folder can be written to and has free space."
+            }
+            ErrorCode::WinePrefix => {
+                "Close the game and any other programs using Wine, then try again."
+            }
+        }
+    }
+}
+
 pub enum PatchErrorLevel {
     /// Displayed as yellow in the GUI
     Low,
@@ -18,24 +130,94 @@ pub enum PatchErrorLevel {
 
 pub trait ToPatchError {
     /// Converts to a PatchError, with level High by default
-    fn to_patch_error(self, friendly_message: &str) -> PatchError;
+    fn to_patch_error(self, friendly_message: &str, code: ErrorCode) -> PatchError;
     /// Converts to a PatchError, with the level specified by a parameter
-    fn to_patch_error_level(self, friendly_message: &str, level: PatchErrorLevel) -> PatchError;
+    fn to_patch_error_level(
+        self,
+        friendly_message: &str,
+        code: ErrorCode,
+        level: PatchErrorLevel,
+    ) -> PatchError;
 }
 
 impl<T> ToPatchError for T
 where
     T: Into<Box<dyn Error>>,
 {
-    fn to_patch_error(self, friendly_message: &str) -> PatchError {
-        self.to_patch_error_level(friendly_message, PatchErrorLevel::High)
+    fn to_patch_error(self, friendly_message: &str, code: ErrorCode) -> PatchError {
+        self.to_patch_error_level(friendly_message, code, PatchErrorLevel::High)
     }
 
-    fn to_patch_error_level(self, friendly_message: &str, level: PatchErrorLevel) -> PatchError {
+    fn to_patch_error_level(
+        self,
+        friendly_message: &str,
+        code: ErrorCode,
+        level: PatchErrorLevel,
+    ) -> PatchError {
         PatchError {
             internal_error: self.into(),
             friendly_message: friendly_message.to_owned(),
+            code,
             level,
         }
     }
 }
+
+/// Describes an error and each error which caused it, outermost first
+pub fn error_chain(error: &dyn Error) -> Vec<String> {
+    let mut chain = vec![error.to_string()];
+    let mut source = error.source();
+    while let Some(error) = source {
+        chain.push(error.to_string());
+        source = error.source();
+    }
+    chain
+}
+
+/// Returned by operations which were stopped early by the user. These are not
+/// failures, so they should not be displayed as errors.
+#[derive(Debug)]
+pub enum Interruption {
+    /// The user cancelled the running operation
+    Cancelled,
+    /// The GUI was closed while the operation was running
+    Closed,
+}
+
+impl std::fmt::Display for Interruption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Interruption::Cancelled => write!(f, "Operation was cancelled by the user"),
+            Interruption::Closed => write!(f, "Operation was interrupted by closing the launcher"),
+        }
+    }
+}
+
+impl Error for Interruption {}
+
+/// Returned when a replacement launcher is not signed by a trusted key. Such
+/// a file must never be executed.
+#[derive(Debug)]
+pub struct UntrustedUpdate(pub String);
+
+impl std::fmt::Display for UntrustedUpdate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Launcher update failed verification: {}", self.0)
+    }
+}
+
+impl Error for UntrustedUpdate {}
+
+/// Returned when the base game archive can't be read, such as when a download
+/// was cut short or mixed up with another version of the file. It has to be
+/// downloaded again.
+#[derive(Debug)]
+pub struct CorruptArchive(pub String);
+
+impl std::fmt::Display for CorruptArchive {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Base game archive is corrupt: {}", self.0)
+    }
+}
+
+impl Error for CorruptArchive {}
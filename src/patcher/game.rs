@@ -0,0 +1,268 @@
+use super::auth::Session;
+use super::constants::GAME_EXE;
+use super::utils::process_env;
+#[cfg(unix)]
+use super::wine;
+#[cfg(unix)]
+use crate::runner;
+use crate::runner::Runner;
+use crate::settings::Settings;
+use std::error::Error;
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use subprocess::{Exec, ExitStatus, NullFile, Popen, PopenConfig, Redirection};
+
+/// How long the game has to keep running before it counts as started. If it
+/// exits with an error before then, it most likely crashed while starting.
+pub const STARTUP_TIME: Duration = Duration::from_secs(10);
+
+/// Everything needed to start the game
+pub struct LaunchCommand {
+    /// The program followed by its arguments
+    pub args: Vec<OsString>,
+    /// Added to the launcher's environment
+    pub env: Vec<(OsString, OsString)>,
+    pub dir: PathBuf,
+    /// The wineserver of the runner, if the game runs in Wine
+    pub wineserver: Option<PathBuf>,
+}
+
+impl LaunchCommand {
+    /// Works out how to start the game in `game_dir` with the launch options
+    /// from the settings. `game_args` come before the player's extra
+    /// arguments.
+    pub fn new(
+        settings: &Settings,
+        runners: &[Runner],
+        game_dir: &Path,
+        game_args: Vec<String>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let eco = game_dir.join(GAME_EXE).into_os_string();
+        let launch = game_args
+            .into_iter()
+            .chain(settings.game.extra_args.iter().cloned())
+            .map(OsString::from);
+        let game_env = settings
+            .game
+            .env
+            .iter()
+            .map(|(key, value)| (OsString::from(key), OsString::from(value)));
+
+        #[cfg(unix)]
+        let (args, env, wineserver) = {
+            let runner =
+                runner::selected(runners, &settings.wine).ok_or("No Wine runner was found")?;
+            let args = [runner.wine.clone().into_os_string(), eco]
+                .into_iter()
+                .chain(launch)
+                .collect();
+
+            // The player's variables can override the prefix, and the ones for
+            // the runner come last since they are the most specific
+            let runner_env = settings
+                .wine
+                .runner_env
+                .get(&runner.id())
+                .into_iter()
+                .flatten()
+                .map(|(key, value)| (OsString::from(key), OsString::from(value)));
+            let mut env: Vec<(OsString, OsString)> = Vec::new();
+            for (key, value) in wine::prefix_env(game_dir)
+                .into_iter()
+                .chain(game_env)
+                .chain(runner_env)
+            {
+                env.retain(|(old_key, _)| old_key != &key);
+                env.push((key, value));
+            }
+            (args, env, Some(runner.wineserver()))
+        };
+        #[cfg(windows)]
+        let (args, env, wineserver) = {
+            // Runners are only used with Wine
+            let _ = runners;
+            let args = std::iter::once(eco).chain(launch).collect();
+            (args, game_env.collect(), None)
+        };
+
+        // Relative directories are inside the game directory
+        let dir = match settings.game.working_dir.trim() {
+            "" => game_dir.to_path_buf(),
+            working_dir => game_dir.join(working_dir),
+        };
+
+        Ok(Self {
+            args,
+            env,
+            dir,
+            wineserver,
+        })
+    }
+
+    /// Shows how the game will be started with the given settings. Login
+    /// details are left as placeholders.
+    pub fn preview(settings: &Settings, runners: &[Runner], game_dir: &Path) -> String {
+        let game_args = if settings.login.enabled {
+            settings.login.launch_args.clone()
+        } else {
+            game_args(settings, None)
+        };
+
+        let command = match Self::new(settings, runners, game_dir, game_args) {
+            Ok(command) => command,
+            Err(why) => return format!("The game can't be started: {why}"),
+        };
+
+        let args: Vec<String> = command.args.iter().map(|arg| quote(arg)).collect();
+        let mut preview = format!("Directory: {}\n", command.dir.display());
+        if !command.env.is_empty() {
+            let env: Vec<String> = command
+                .env
+                .iter()
+                .map(|(key, value)| format!("{}={}", key.to_string_lossy(), quote(value)))
+                .collect();
+            preview.push_str(&format!("Environment: {}\n", env.join(" ")));
+        }
+        preview.push_str(&format!("Command: {}", args.join(" ")));
+        preview
+    }
+}
+
+/// Gets the arguments for the game. Logged in players are passed on to the
+/// game as configured in the login settings.
+pub fn game_args(settings: &Settings, session: Option<&Session>) -> Vec<String> {
+    match session {
+        Some(session) => settings
+            .login
+            .launch_args
+            .iter()
+            .map(|arg| {
+                arg.replace("{username}", &session.username)
+                    .replace("{token}", &session.token)
+            })
+            .collect(),
+        None => vec!["/launch".to_string()],
+    }
+}
+
+/// Quotes an argument for display if it would be unclear where it ends
+fn quote(arg: &OsStr) -> String {
+    let arg = arg.to_string_lossy();
+    if !arg.is_empty() && !arg.contains(|c: char| c.is_whitespace() || c == '"') {
+        return arg.to_string();
+    }
+    format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// A running copy of the game
+pub struct GameProcess {
+    /// The process being watched. Under Wine this is first the wine process,
+    /// then `wineserver -w` once wine exits, if no other game is running.
+    popen: Popen,
+    /// The wineserver of the game's prefix, with its environment. wine exits
+    /// when eco.exe does, but the game can keep running in other processes of
+    /// the prefix. The wineserver only exits once every one of them has.
+    wineserver: Option<(PathBuf, Vec<(OsString, OsString)>)>,
+    /// Whether `popen` is the wineserver rather than the game
+    watching_prefix: bool,
+    started: Instant,
+    /// Where the output of the game is written
+    pub log_path: PathBuf,
+    /// The name of the game client from the settings, or `None` for the main
+    /// game
+    pub client: Option<String>,
+}
+
+/// How the game ended
+pub enum GameExit {
+    Normal,
+    /// The game exited with an error, described for the player
+    Crashed(String),
+}
+
+impl GameProcess {
+    /// Starts the game, writing its output to `log_path`. With Wine, the
+    /// wineserver is used to tell when every process of the game has exited.
+    pub fn start(
+        command: LaunchCommand,
+        log_path: PathBuf,
+        client: Option<String>,
+    ) -> Result<Self, Box<dyn Error>> {
+        if let Some(log_dir) = log_path.parent() {
+            std::fs::create_dir_all(log_dir)?;
+        }
+        let log = std::fs::File::create(&log_path)?;
+
+        let config = PopenConfig {
+            env: process_env(&command.env),
+            cwd: Some(command.dir.into_os_string()),
+            stdout: Redirection::File(log),
+            stderr: Redirection::Merge,
+            ..PopenConfig::default()
+        };
+        let mut popen = Popen::create(&command.args, config)?;
+        // The game must keep running if the launcher closes
+        popen.detach();
+
+        Ok(Self {
+            popen,
+            wineserver: command
+                .wineserver
+                .map(|wineserver| (wineserver, command.env)),
+            watching_prefix: false,
+            started: Instant::now(),
+            log_path,
+            client,
+        })
+    }
+
+    /// Whether the game has been running long enough to count as started
+    pub fn has_started(&self) -> bool {
+        self.started.elapsed() >= STARTUP_TIME
+    }
+
+    /// Checks whether the game has exited, without waiting. `alone` is
+    /// whether this is the only game running. Every game shares the same Wine
+    /// prefix, so the wineserver can only tell when the last of them exits.
+    pub fn poll(&mut self, alone: bool) -> Result<Option<GameExit>, Box<dyn Error>> {
+        let status = match self.popen.poll() {
+            Some(status) => status,
+            // The wineserver now waits for another game too
+            None if self.watching_prefix && !alone => return Ok(Some(GameExit::Normal)),
+            None => return Ok(None),
+        };
+
+        if !status.success() {
+            return Ok(Some(GameExit::Crashed(exit_string(status))));
+        }
+
+        // Keep watching the game's other processes through the wineserver
+        if let Some((wineserver, env)) = self.wineserver.take().filter(|_| alone) {
+            self.popen = Exec::cmd(wineserver)
+                .arg("-w")
+                .env_extend(&env)
+                .stdout(NullFile)
+                .stderr(NullFile)
+                .popen()?;
+            self.popen.detach();
+            self.watching_prefix = true;
+            return Ok(None);
+        }
+
+        Ok(Some(GameExit::Normal))
+    }
+}
+
+/// Describes how a process exited, such as `exit code 3`
+fn exit_string(status: ExitStatus) -> String {
+    match status {
+        // Windows exit codes are often NTSTATUS values, which read better
+        // in hex
+        ExitStatus::Exited(code) if code > 0xFFFF => format!("exit code {code:#010X}"),
+        ExitStatus::Exited(code) => format!("exit code {code}"),
+        ExitStatus::Signaled(signal) => format!("signal {signal}"),
+        ExitStatus::Other(code) => format!("status {code}"),
+        ExitStatus::Undetermined => "an unknown status".to_string(),
+    }
+}
@@ -0,0 +1,240 @@
+use super::constants::{GAME_INI, INI_BACKUP_DIR, INI_PROFILE_DIR, MAX_INI_BACKUPS};
+use super::utils::check_entry_name;
+use crate::ini::{self, IniChange, IniEncoding, IniFile};
+use crate::message::IniBackup;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+/// Backups are named after the time they were made, like
+/// `eco-20240131-235959.123.ini`
+const BACKUP_TIME_FORMAT: &str = "%Y%m%d-%H%M%S%.3f";
+
+/// Reads the game's eco.ini
+pub fn read(game_dir: &Path) -> Result<IniFile, Box<dyn Error>> {
+    let path = game_dir.join(GAME_INI);
+    if !path.exists() {
+        return Err(format!(
+            "{GAME_INI} has not been created yet. Start the game once to create it."
+        )
+        .into());
+    }
+    IniFile::parse(&std::fs::read(path)?)
+}
+
+/// Changes values in eco.ini, after checking them and backing up the file
+pub fn write(game_dir: &Path, changes: &[IniChange]) -> Result<(), Box<dyn Error>> {
+    for change in changes {
+        ini::validate(&change.section, &change.key, &change.value)
+            .map_err(|why| format!("Invalid value for {}: {why}", change.key))?;
+    }
+
+    // The game may have changed the file since it was shown to the player, so
+    // only the changed values are written
+    let mut ini = read(game_dir)?;
+    for change in changes {
+        ini.set(&change.section, &change.key, &change.value);
+    }
+
+    replace(game_dir, &ini.to_bytes())
+}
+
+/// What `repair` did to eco.ini
+pub enum Repair {
+    /// The file was already fine
+    Unchanged,
+    /// The game hasn't created the file yet
+    Missing,
+    /// The `[CheckECO]` section was added to the file, which was written back
+    /// in the same encoding
+    Repaired(IniEncoding),
+}
+
+/// Emil Chronicle Online expects a section in its eco.ini which is like:
+///
+/// [CheckECO]
+/// OK
+///
+/// If this is not present, the game will ignore its eco.ini and load only
+/// default configurations. The game is expected to write this before it
+/// shuts down, but especially on Linux with Wine, this doesn't seem
+/// to happen. If we add the section ourselves, then we can ensure that
+/// Linux users' configurations actually work.
+///
+/// When default configurations are loaded, undesirable things happen such
+/// as not saving game-window size, using the incorrect in-game window
+/// themes, and having the game be totally silent due to the volume sliders
+/// being set to 0%.
+pub fn repair(game_dir: &Path) -> Result<Repair, Box<dyn Error>> {
+    if !game_dir.join(GAME_INI).exists() {
+        return Ok(Repair::Missing);
+    }
+
+    let mut ini = read(game_dir)?;
+    if ini.has_section("CheckECO") {
+        return Ok(Repair::Unchanged);
+    }
+    ini.add_section("CheckECO", &["OK"]);
+
+    replace(game_dir, &ini.to_bytes())?;
+    Ok(Repair::Repaired(ini.encoding()))
+}
+
+/// Writes new contents to eco.ini. The old and the new contents are both
+/// backed up, so either can be restored.
+fn replace(game_dir: &Path, data: &[u8]) -> Result<(), Box<dyn Error>> {
+    backup(game_dir)?;
+    std::fs::write(game_dir.join(GAME_INI), data)?;
+    backup(game_dir)?;
+    Ok(())
+}
+
+/// Copies eco.ini into the backup directory if it has changed since the last
+/// backup, which catches changes made by the game as well. Only the newest
+/// `MAX_INI_BACKUPS` backups are kept. Returns the path of the new backup, if
+/// one was made.
+pub fn backup(game_dir: &Path) -> Result<Option<PathBuf>, Box<dyn Error>> {
+    let ini_path = game_dir.join(GAME_INI);
+    if !ini_path.is_file() {
+        return Ok(None);
+    }
+    let data = std::fs::read(&ini_path)?;
+
+    let backup_dir = game_dir.join(INI_BACKUP_DIR);
+    let mut names = backup_names(game_dir)?;
+    if let Some(newest) = names.last() {
+        if std::fs::read(backup_dir.join(newest)).ok().as_ref() == Some(&data) {
+            return Ok(None);
+        }
+    }
+
+    std::fs::create_dir_all(&backup_dir)?;
+    let (name, backup_path) = loop {
+        let name = format!(
+            "eco-{}.ini",
+            chrono::Local::now().format(BACKUP_TIME_FORMAT)
+        );
+        let backup_path = backup_dir.join(&name);
+        // Backups made before and after a change can be very close together
+        if !backup_path.exists() {
+            break (name, backup_path);
+        }
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    };
+    std::fs::write(&backup_path, data)?;
+    names.push(name);
+
+    let excess = names.len().saturating_sub(MAX_INI_BACKUPS);
+    for old in &names[..excess] {
+        if let Err(why) = std::fs::remove_file(backup_dir.join(old)) {
+            log::warn!("Failed to remove old {GAME_INI} backup {old}: {why}");
+        }
+    }
+    Ok(Some(backup_path))
+}
+
+/// Lists the backups of eco.ini, newest first
+pub fn backups(game_dir: &Path) -> Result<Vec<IniBackup>, Box<dyn Error>> {
+    let backups = backup_names(game_dir)?
+        .into_iter()
+        .rev()
+        .map(|name| {
+            let time = name
+                .strip_prefix("eco-")
+                .and_then(|name| name.strip_suffix(".ini"))
+                .and_then(|time| {
+                    chrono::NaiveDateTime::parse_from_str(time, BACKUP_TIME_FORMAT).ok()
+                });
+            IniBackup {
+                saved: match time {
+                    Some(time) => time.format("%Y-%m-%d %H:%M:%S").to_string(),
+                    None => name.clone(),
+                },
+                name,
+            }
+        })
+        .collect();
+    Ok(backups)
+}
+
+/// Puts a backup of eco.ini back in place
+pub fn restore(game_dir: &Path, name: &str) -> Result<(), Box<dyn Error>> {
+    check_entry_name(name).map_err(|why| format!("Invalid backup name {name:?}: {why}"))?;
+    let data = std::fs::read(game_dir.join(INI_BACKUP_DIR).join(name))
+        .map_err(|why| format!("Could not read the backup {name}: {why}"))?;
+    replace(game_dir, &data)
+}
+
+/// File names of the backups, oldest first. The names sort by the time they
+/// were made.
+fn backup_names(game_dir: &Path) -> Result<Vec<String>, Box<dyn Error>> {
+    ini_files(&game_dir.join(INI_BACKUP_DIR))
+}
+
+/// Lists the names of the saved eco.ini profiles, in order
+pub fn profiles(game_dir: &Path) -> Result<Vec<String>, Box<dyn Error>> {
+    let profiles = ini_files(&game_dir.join(INI_PROFILE_DIR))?
+        .into_iter()
+        .filter_map(|name| name.strip_suffix(".ini").map(String::from))
+        .collect();
+    Ok(profiles)
+}
+
+/// Saves the current eco.ini as a profile, replacing the profile if it
+/// already exists
+pub fn save_profile(game_dir: &Path, profile: &str) -> Result<(), Box<dyn Error>> {
+    let profile_path = profile_path(game_dir, profile)?;
+    let ini_path = game_dir.join(GAME_INI);
+    if !ini_path.is_file() {
+        return Err(format!("{GAME_INI} has not been created yet").into());
+    }
+    std::fs::create_dir_all(game_dir.join(INI_PROFILE_DIR))?;
+    std::fs::copy(ini_path, profile_path)?;
+    Ok(())
+}
+
+pub fn delete_profile(game_dir: &Path, profile: &str) -> Result<(), Box<dyn Error>> {
+    std::fs::remove_file(profile_path(game_dir, profile)?)?;
+    Ok(())
+}
+
+/// Replaces eco.ini with a profile
+pub fn use_profile(game_dir: &Path, profile: &str) -> Result<(), Box<dyn Error>> {
+    let profile_path = profile_path(game_dir, profile)?;
+    if !profile_path.is_file() {
+        return Err(format!("The {GAME_INI} profile {profile:?} was not found").into());
+    }
+    replace(game_dir, &std::fs::read(profile_path)?)
+}
+
+fn profile_path(game_dir: &Path, profile: &str) -> Result<PathBuf, Box<dyn Error>> {
+    check_entry_name(profile)
+        .map_err(|why| format!("Invalid {GAME_INI} profile name {profile:?}: {why}"))?;
+    Ok(game_dir
+        .join(INI_PROFILE_DIR)
+        .join(format!("{profile}.ini")))
+}
+
+/// Names of the `.ini` files in a directory, sorted. A missing directory has
+/// none.
+fn ini_files(dir: &Path) -> Result<Vec<String>, Box<dyn Error>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(why) if why.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(why) => return Err(why.into()),
+    };
+
+    let mut names = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        if !entry.file_type()?.is_file() {
+            continue;
+        }
+        if let Some(name) = entry.file_name().to_str() {
+            if name.to_ascii_lowercase().ends_with(".ini") {
+                names.push(name.to_string());
+            }
+        }
+    }
+    names.sort();
+    Ok(names)
+}
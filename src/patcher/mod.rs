@@ -1,9 +1,26 @@
 mod worker;
+pub use constants::LOG_DIR;
+pub use game::LaunchCommand;
+pub use utils::get_platform;
 pub use worker::PatchWorker;
 pub use worker::RunState;
 
+mod auth;
+mod branding;
+mod bundle;
+mod cache;
 mod check_patches;
 mod constants;
+mod diagnostics;
 mod download;
 mod error;
+mod game;
+mod game_ini;
+mod news;
+mod signature;
+mod state;
+mod status;
+mod transport;
 mod utils;
+#[cfg(unix)]
+mod wine;
@@ -0,0 +1,66 @@
+use super::cache::{cache_name, fetch_cached};
+use super::constants::{CACHE_DIR, NEWS, NEWS_IMAGE_DIR};
+use super::error::Interruption;
+use super::PatchWorker;
+use crate::news::{image_sources, News, NewsFile};
+use std::collections::HashMap;
+
+/// Gets the news from the patch server and sends it to the GUI. If the server
+/// can't be reached, or `online` is false, the copy from last time is shown
+/// instead. Problems with the news are never fatal, so the only error is the
+/// user interrupting the download.
+pub fn update_news(worker: &mut PatchWorker, online: bool) -> Result<(), Interruption> {
+    let cache_dir = worker.self_dir.join(CACHE_DIR);
+    let news_url = worker.news_url.clone();
+
+    // News is signed like the link buttons, since it can link anywhere too
+    let json_bytes = match fetch_cached(worker, &news_url, &cache_dir.join(NEWS), online, true)? {
+        Some(json_bytes) => json_bytes,
+        None => return Ok(()),
+    };
+
+    let mut news_file = match serde_json::from_slice::<NewsFile>(&json_bytes) {
+        Ok(news_file) => news_file,
+        Err(why) => {
+            log::warn!("Could not parse news: {why}");
+            return Ok(());
+        }
+    };
+
+    // Image locations are relative to news.json
+    let mut images = HashMap::new();
+    for item in &news_file.items {
+        for src in image_sources(&item.body) {
+            if images.contains_key(&src) {
+                continue;
+            }
+
+            let (url, file_name) = match (news_url.join(&src), cache_name(&src)) {
+                (Ok(url), Some(file_name)) => (url, file_name),
+                _ => {
+                    log::warn!("Ignoring news image with unusable location {src:?}");
+                    continue;
+                }
+            };
+
+            let cache_path = cache_dir.join(NEWS_IMAGE_DIR).join(file_name);
+            if let Some(data) = fetch_cached(worker, &url, &cache_path, online, false)? {
+                images.insert(src, data);
+            }
+        }
+    }
+
+    // Items are new if they weren't shown the last time the launcher ran
+    for item in &mut news_file.items {
+        item.is_new = !worker.seen_news_at_launch.contains(&item.id);
+    }
+    worker.state.seen_news = news_file.items.iter().map(|item| item.id.clone()).collect();
+    worker.save_state();
+
+    worker.send_news(News {
+        items: news_file.items,
+        images,
+    });
+
+    Ok(())
+}
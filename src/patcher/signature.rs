@@ -0,0 +1,226 @@
+use ed25519_dalek::{Signature, VerifyingKey};
+use serde::Deserialize;
+use std::error::Error;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Provides TRUSTED_KEYS, which is generated by build.rs from the keys directory
+include!(concat!(env!("OUT_DIR"), "/trusted_keys.rs"));
+
+/// The program can be built to accept unsigned files from the patch server
+/// using the "skip_signature_check" feature. This is only meant for testing
+/// against private patch servers.
+pub const CHECK_SIGNATURES: bool = !cfg!(feature = "skip_signature_check");
+
+/// A detached signature, stored on the patch server next to the file it signs.
+///
+/// It can hold signatures from several keys. While a signing key is being
+/// replaced, the server signs with both the old and the new key so that
+/// launchers trusting either of them keep working.
+#[derive(Deserialize)]
+struct SignatureFile {
+    /// Unix time after which the signature is no longer accepted, so that an
+    /// old copy of a file can't be sent again. It is signed along with the
+    /// file.
+    #[serde(default)]
+    expires: Option<u64>,
+    signatures: Vec<KeySignature>,
+}
+
+#[derive(Deserialize)]
+struct KeySignature {
+    /// Base64 Ed25519 public key which made the signature
+    key: String,
+    /// Base64 Ed25519 signature of the signed file
+    signature: String,
+}
+
+/// Checks that `data` was signed by one of the keys built into the launcher.
+/// `signature_file` is the contents of the detached signature for `data`.
+pub fn verify(data: &[u8], signature_file: &[u8]) -> Result<(), Box<dyn Error>> {
+    verify_with(data, signature_file, false, TRUSTED_KEYS)
+}
+
+/// Like `verify`, for documents from the patch server which change over time,
+/// such as status.json. Their signatures must expire, and must not have
+/// expired yet.
+pub fn verify_document(data: &[u8], signature_file: &[u8]) -> Result<(), Box<dyn Error>> {
+    verify_with(data, signature_file, true, TRUSTED_KEYS)
+}
+
+fn verify_with(
+    data: &[u8],
+    signature_file: &[u8],
+    require_expiry: bool,
+    trusted_keys: &[&str],
+) -> Result<(), Box<dyn Error>> {
+    if trusted_keys.is_empty() {
+        return Err("This launcher was built without any trusted signing keys".into());
+    }
+
+    let signature_file = serde_json::from_slice::<SignatureFile>(signature_file)?;
+
+    // The expiry is part of what is signed, so it can't be changed
+    let message = match signature_file.expires {
+        Some(expires) => {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+            if now > expires {
+                return Err(format!(
+                    "The signature expired at {expires} (Unix time), so this may be an old \
+                     copy of the file"
+                )
+                .into());
+            }
+            [format!("aeco-expires:{expires}\n").as_bytes(), data].concat()
+        }
+        None if require_expiry => return Err("The signature has no expiry".into()),
+        None => data.to_vec(),
+    };
+
+    // While keys are being rotated, the signature from one trusted key may
+    // be stale while another one is valid, so any valid one is enough
+    let mut invalid_key = None;
+    for key_signature in &signature_file.signatures {
+        // Signatures from keys we don't know about prove nothing
+        if !trusted_keys.contains(&key_signature.key.as_str()) {
+            continue;
+        }
+
+        if check_signature(&message, key_signature).is_ok() {
+            return Ok(());
+        }
+        invalid_key = Some(&key_signature.key);
+    }
+
+    // A bad signature from a trusted key means the file was tampered with
+    match invalid_key {
+        Some(key) => Err(format!("Signature by trusted key {key} is invalid").into()),
+        None => Err("The file is not signed by any trusted key".into()),
+    }
+}
+
+fn check_signature(message: &[u8], key_signature: &KeySignature) -> Result<(), Box<dyn Error>> {
+    let key_bytes = base64::decode(&key_signature.key)?;
+    let key = VerifyingKey::try_from(key_bytes.as_slice())?;
+    let signature_bytes = base64::decode(&key_signature.signature)?;
+    let signature = Signature::from_slice(&signature_bytes)?;
+    Ok(key.verify_strict(message, &signature)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::verify_with;
+    use ed25519_dalek::{Signer, SigningKey};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    const DATA: &[u8] = b"{\"motd\":\"Welcome\"}";
+
+    /// A throwaway key, made from a fixed seed
+    fn signing_key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    fn public_key(key: &SigningKey) -> String {
+        base64::encode(key.verifying_key().as_bytes())
+    }
+
+    /// Signs `data` the way the patch server does
+    fn sign(key: &SigningKey, data: &[u8], expires: Option<u64>) -> String {
+        let message = match expires {
+            Some(expires) => [format!("aeco-expires:{expires}\n").as_bytes(), data].concat(),
+            None => data.to_vec(),
+        };
+        base64::encode(key.sign(&message).to_bytes())
+    }
+
+    /// A signature file with one signature per `(key, signature)`
+    fn signature_file(expires: Option<u64>, signatures: &[(&SigningKey, String)]) -> Vec<u8> {
+        let signatures: Vec<_> = signatures
+            .iter()
+            .map(|(key, signature)| {
+                serde_json::json!({ "key": public_key(key), "signature": signature })
+            })
+            .collect();
+        let mut file = serde_json::json!({ "signatures": signatures });
+        if let Some(expires) = expires {
+            file["expires"] = expires.into();
+        }
+        serde_json::to_vec(&file).unwrap()
+    }
+
+    fn in_an_hour() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            + 3600
+    }
+
+    #[test]
+    fn accepts_valid_signatures() {
+        let key = signing_key(1);
+        let trusted = [public_key(&key)];
+        let trusted: Vec<&str> = trusted.iter().map(String::as_str).collect();
+        let expires = in_an_hour();
+
+        let file = signature_file(Some(expires), &[(&key, sign(&key, DATA, Some(expires)))]);
+        assert!(verify_with(DATA, &file, true, &trusted).is_ok());
+        let file = signature_file(None, &[(&key, sign(&key, DATA, None))]);
+        assert!(verify_with(DATA, &file, false, &trusted).is_ok());
+    }
+
+    #[test]
+    fn rejects_tampering() {
+        let key = signing_key(1);
+        let trusted = [public_key(&key)];
+        let trusted: Vec<&str> = trusted.iter().map(String::as_str).collect();
+        let expires = in_an_hour();
+        let signature = sign(&key, DATA, Some(expires));
+
+        let file = signature_file(Some(expires), &[(&key, signature.clone())]);
+        assert!(verify_with(b"{\"motd\":\"Goodbye\"}", &file, true, &trusted).is_err());
+        // The expiry is signed too
+        let file = signature_file(Some(expires + 1), &[(&key, signature)]);
+        assert!(verify_with(DATA, &file, true, &trusted).is_err());
+    }
+
+    #[test]
+    fn rejects_expired_signatures() {
+        let key = signing_key(1);
+        let trusted = [public_key(&key)];
+        let trusted: Vec<&str> = trusted.iter().map(String::as_str).collect();
+
+        let file = signature_file(Some(1), &[(&key, sign(&key, DATA, Some(1)))]);
+        assert!(verify_with(DATA, &file, false, &trusted).is_err());
+        // Documents must expire
+        let file = signature_file(None, &[(&key, sign(&key, DATA, None))]);
+        assert!(verify_with(DATA, &file, true, &trusted).is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        let (key, unknown) = (signing_key(1), signing_key(2));
+        let trusted = [public_key(&key)];
+        let trusted: Vec<&str> = trusted.iter().map(String::as_str).collect();
+
+        let file = signature_file(None, &[(&unknown, sign(&unknown, DATA, None))]);
+        assert!(verify_with(DATA, &file, false, &trusted).is_err());
+        assert!(verify_with(DATA, &file, false, &[]).is_err());
+    }
+
+    #[test]
+    fn accepts_any_valid_trusted_signature() {
+        let (old, new) = (signing_key(1), signing_key(2));
+        let trusted = [public_key(&old), public_key(&new)];
+        let trusted: Vec<&str> = trusted.iter().map(String::as_str).collect();
+
+        // The old key's signature is stale, the new one's is valid
+        let file = signature_file(
+            None,
+            &[
+                (&old, sign(&old, b"an older file", None)),
+                (&new, sign(&new, DATA, None)),
+            ],
+        );
+        assert!(verify_with(DATA, &file, false, &trusted).is_ok());
+    }
+}
@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Name of the state file, which is kept next to the launcher
+pub const STATE_FILE: &str = "launcher-state.json";
+
+/// Things the launcher remembers between runs. Unlike the settings, these are
+/// not meant to be edited by users.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LauncherState {
+    /// When all game files were last checked and found up to date, in seconds
+    /// since the Unix epoch. This is cleared as soon as any game file is
+    /// changed, and set again once patching has finished.
+    pub last_patched: Option<u64>,
+    /// IDs of the news items which were shown the last time the launcher ran
+    pub seen_news: Vec<String>,
+}
+
+impl LauncherState {
+    /// Loads the state file from `dir`. Problems with the file are not fatal,
+    /// the launcher just starts over without any remembered state.
+    pub fn load(dir: &Path) -> Self {
+        let path = dir.join(STATE_FILE);
+        if !path.exists() {
+            return Self::default();
+        }
+
+        let result = std::fs::read(&path)
+            .map_err(Box::<dyn Error>::from)
+            .and_then(|json_bytes| Ok(serde_json::from_slice(&json_bytes)?));
+
+        result.unwrap_or_else(|why| {
+            log::warn!("Could not load launcher state: {why}");
+            Self::default()
+        })
+    }
+
+    /// Writes the state file to `dir`
+    pub fn save(&self, dir: &Path) -> Result<(), Box<dyn Error>> {
+        let json_bytes = serde_json::to_vec_pretty(self)?;
+        std::fs::write(dir.join(STATE_FILE), json_bytes)?;
+        Ok(())
+    }
+}
+
+/// Gets the current time in seconds since the Unix epoch
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
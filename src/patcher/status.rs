@@ -0,0 +1,114 @@
+use aeco_patch_config::status::ServerStatus;
+use serde::Deserialize;
+
+/// The contents of status.json. Servers may send either just a
+/// `ServerStatus`, or a `StatusDocument` with extra information.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StatusFile {
+    Extended(StatusDocument),
+    Basic(ServerStatus),
+}
+
+/// Everything the server has to say about its current state
+#[derive(Deserialize)]
+pub struct StatusDocument {
+    pub status: ServerStatus,
+    /// Message of the day, shown to every player
+    #[serde(default)]
+    pub motd: Option<String>,
+    /// Scheduled or ongoing maintenance
+    #[serde(default)]
+    pub maintenance: Option<MaintenanceWindow>,
+    /// The oldest launcher version which may be used, such as `1.0.1`
+    #[serde(default)]
+    pub min_launcher_version: Option<String>,
+    /// If the launcher is older than `min_launcher_version`, it should update
+    /// itself from the patch server instead of refusing to continue
+    #[serde(default)]
+    pub force_update: bool,
+}
+
+/// Times are in seconds since the Unix epoch
+#[derive(Deserialize, Clone, Copy)]
+pub struct MaintenanceWindow {
+    #[serde(default)]
+    pub start: Option<u64>,
+    #[serde(default)]
+    pub end: Option<u64>,
+}
+
+impl StatusDocument {
+    /// Parses status.json in either of the formats the server may use
+    pub fn from_slice(json_bytes: &[u8]) -> Result<Self, serde_json::Error> {
+        Ok(match serde_json::from_slice::<StatusFile>(json_bytes)? {
+            StatusFile::Extended(document) => document,
+            StatusFile::Basic(status) => StatusDocument {
+                status,
+                motd: None,
+                maintenance: None,
+                min_launcher_version: None,
+                force_update: false,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StatusDocument;
+    use aeco_patch_config::status::ServerStatus;
+
+    fn status_json(status: ServerStatus) -> serde_json::Value {
+        serde_json::to_value(status).unwrap()
+    }
+
+    #[test]
+    fn parses_basic_status() {
+        let json = serde_json::to_vec(&status_json(ServerStatus::Maintenance)).unwrap();
+        let document = StatusDocument::from_slice(&json).unwrap();
+        assert!(matches!(document.status, ServerStatus::Maintenance));
+        assert!(document.motd.is_none());
+        assert!(document.maintenance.is_none());
+        assert!(document.min_launcher_version.is_none());
+        assert!(!document.force_update);
+    }
+
+    #[test]
+    fn parses_extended_status() {
+        let json = serde_json::json!({
+            "status": status_json(ServerStatus::Online),
+            "motd": "Welcome back",
+            "maintenance": { "start": 100, "end": 200 },
+            "min_launcher_version": "1.2.0",
+            "force_update": true,
+        });
+        let document = StatusDocument::from_slice(&serde_json::to_vec(&json).unwrap()).unwrap();
+        assert!(matches!(document.status, ServerStatus::Online));
+        assert_eq!(document.motd.as_deref(), Some("Welcome back"));
+        let maintenance = document.maintenance.unwrap();
+        assert_eq!((maintenance.start, maintenance.end), (Some(100), Some(200)));
+        assert_eq!(document.min_launcher_version.as_deref(), Some("1.2.0"));
+        assert!(document.force_update);
+    }
+
+    #[test]
+    fn fills_in_missing_fields() {
+        let json = serde_json::json!({
+            "status": status_json(ServerStatus::Maintenance),
+            "maintenance": { "end": 200 },
+        });
+        let document = StatusDocument::from_slice(&serde_json::to_vec(&json).unwrap()).unwrap();
+        assert!(matches!(document.status, ServerStatus::Maintenance));
+        let maintenance = document.maintenance.unwrap();
+        assert_eq!((maintenance.start, maintenance.end), (None, Some(200)));
+        assert!(document.min_launcher_version.is_none());
+        assert!(!document.force_update);
+    }
+
+    #[test]
+    fn rejects_other_documents() {
+        assert!(StatusDocument::from_slice(b"{\"motd\":\"No status\"}").is_err());
+        assert!(StatusDocument::from_slice(b"not json").is_err());
+    }
+}
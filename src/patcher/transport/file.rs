@@ -0,0 +1,66 @@
+use super::{Body, Download, Transport};
+use reqwest::Url;
+use std::error::Error;
+use std::io::{Read, Seek, SeekFrom};
+use std::time::UNIX_EPOCH;
+
+/// How much of a file is read at a time
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Reads patches from a directory laid out like the patch server, such as a
+/// copy on a USB drive or a mounted LAN share
+pub struct FileTransport;
+
+impl Transport for FileTransport {
+    fn fetch(
+        &self,
+        url: &Url,
+        offset: u64,
+        validator: Option<&str>,
+    ) -> Result<Download, Box<dyn Error>> {
+        let path = url
+            .to_file_path()
+            .map_err(|_| format!("Not a local file: {url}"))?;
+
+        let mut file = std::fs::File::open(&path)
+            .map_err(|why| format!("Could not open {}: {why}", path.display()))?;
+
+        // A local file can continue where a download left off, as long as it
+        // hasn't changed since
+        let metadata = file.metadata()?;
+        let size = metadata.len();
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |time| time.as_nanos());
+        let current_validator = format!("{size}-{modified}");
+        let resumed = offset <= size && validator == Some(current_validator.as_str());
+        let offset = if resumed { offset } else { 0 };
+        file.seek(SeekFrom::Start(offset))?;
+
+        Ok(Download {
+            resumed,
+            remaining_size: Some(size - offset),
+            validator: Some(current_validator),
+            body: Box::new(FileBody { file }),
+        })
+    }
+}
+
+struct FileBody {
+    file: std::fs::File,
+}
+
+impl Body for FileBody {
+    fn next_chunk(&mut self) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+        let mut chunk = vec![0u8; CHUNK_SIZE];
+        let read = self.file.read(&mut chunk)?;
+        if read == 0 {
+            return Ok(None);
+        }
+
+        chunk.truncate(read);
+        Ok(Some(chunk))
+    }
+}
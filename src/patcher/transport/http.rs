@@ -0,0 +1,282 @@
+use super::{Body, Download, Transport};
+use crate::settings::{NetworkSettings, ProxyMode};
+use crate::version::version_summary;
+use futures_util::{Stream, StreamExt};
+use reqwest::header::{HeaderMap, CONTENT_RANGE, ETAG, IF_RANGE, LAST_MODIFIED, RANGE};
+use reqwest::{NoProxy, Proxy, StatusCode, Url};
+use std::error::Error;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+type ChunkStream = Pin<Box<dyn Stream<Item = reqwest::Result<Vec<u8>>> + Send>>;
+
+/// Downloads patches from a web server
+pub struct HttpTransport {
+    client: reqwest::Client,
+    runtime: Arc<tokio::runtime::Runtime>,
+    read_timeout: Duration,
+}
+
+impl HttpTransport {
+    pub fn new(settings: &NetworkSettings) -> Result<Self, Box<dyn Error>> {
+        let client = build_client(settings)?;
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()?;
+
+        Ok(Self {
+            client,
+            runtime: Arc::new(runtime),
+            read_timeout: Duration::from_secs(settings.read_timeout),
+        })
+    }
+}
+
+impl Transport for HttpTransport {
+    fn fetch(
+        &self,
+        url: &Url,
+        offset: u64,
+        validator: Option<&str>,
+    ) -> Result<Download, Box<dyn Error>> {
+        // Only ask for the part of the file that is still missing. Without a
+        // validator, there is no way to tell whether the file changed since
+        // the earlier download, so it starts over.
+        let mut request = self.client.get(url.clone());
+        let resuming = match validator {
+            Some(validator) if offset > 0 => {
+                request = request
+                    .header(RANGE, format!("bytes={offset}-"))
+                    .header(IF_RANGE, validator);
+                true
+            }
+            _ => false,
+        };
+
+        // Request URL
+        let response = self.runtime.block_on(request.send())?;
+
+        // Check response status
+        let status = response.status();
+        if resuming && status == StatusCode::RANGE_NOT_SATISFIABLE {
+            // The range starts at or after the end of the file. Only if it is
+            // exactly at the end is there nothing left to download.
+            let size = content_range(response.headers()).and_then(|(_, size)| size);
+            if size == Some(offset) {
+                return Ok(Download::finished(validator.map(str::to_string)));
+            }
+            log::info!("The partial download of {url} doesn't match, starting over");
+            return self.fetch(url, 0, None);
+        }
+        if !status.is_success() {
+            return Err(format!("URL request failed: {status}").into());
+        }
+
+        let resumed = is_resumed(status, response.headers(), offset)?;
+
+        let validator = response_validator(response.headers())
+            .or_else(|| validator.filter(|_| resumed).map(str::to_string));
+
+        // The server doesn't need to tell us how long the content is.
+        let remaining_size = response.content_length();
+
+        let stream = response
+            .bytes_stream()
+            .map(|chunk| chunk.map(|bytes| bytes.to_vec()));
+
+        Ok(Download {
+            resumed,
+            remaining_size,
+            validator,
+            body: Box::new(HttpBody {
+                stream: Box::pin(stream),
+                runtime: self.runtime.clone(),
+                read_timeout: self.read_timeout,
+            }),
+        })
+    }
+}
+
+/// Whether a successful response continues the file from `offset`. The
+/// server is allowed to ignore the range and send the whole file, which it
+/// also does if the file changed, and then the download starts over.
+fn is_resumed(status: StatusCode, headers: &HeaderMap, offset: u64) -> Result<bool, String> {
+    if status != StatusCode::PARTIAL_CONTENT {
+        return Ok(false);
+    }
+
+    let start = content_range(headers).map(|(start, _)| start);
+    if start != Some(offset) {
+        return Err(format!(
+            "Asked for the file from byte {offset}, but the server sent {:?}",
+            headers.get(CONTENT_RANGE)
+        ));
+    }
+    Ok(true)
+}
+
+/// Reads `Content-Range: bytes 100-199/1000` as the start and the size of the
+/// whole file, or `bytes */1000` as just the size
+fn content_range(headers: &HeaderMap) -> Option<(u64, Option<u64>)> {
+    let value = headers.get(CONTENT_RANGE)?.to_str().ok()?;
+    let (range, size) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let size = size.parse().ok();
+    match range {
+        "*" => Some((0, size)),
+        range => Some((range.split_once('-')?.0.parse().ok()?, size)),
+    }
+}
+
+/// What identifies this version of the file, for `If-Range`. Weak ETags
+/// can't be used for that.
+fn response_validator(headers: &HeaderMap) -> Option<String> {
+    let etag = headers
+        .get(ETAG)
+        .and_then(|value| value.to_str().ok())
+        .filter(|etag| !etag.starts_with("W/"));
+    etag.or_else(|| {
+        headers
+            .get(LAST_MODIFIED)
+            .and_then(|value| value.to_str().ok())
+    })
+    .map(str::to_string)
+}
+
+struct HttpBody {
+    stream: ChunkStream,
+    runtime: Arc<tokio::runtime::Runtime>,
+    read_timeout: Duration,
+}
+
+impl Body for HttpBody {
+    /// Fails if the server does not send anything for longer than the
+    /// configured read timeout
+    fn next_chunk(&mut self) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+        let read_timeout = self.read_timeout;
+        let stream = &mut self.stream;
+        let chunk = self
+            .runtime
+            .block_on(async { tokio::time::timeout(read_timeout, stream.next()).await })
+            .map_err(|_| {
+                format!(
+                    "No data was received for {} seconds",
+                    read_timeout.as_secs()
+                )
+            })?;
+
+        match chunk {
+            Some(bytes) => Ok(Some(bytes?)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Creates the client used for all requests to the patch server and the login
+/// server
+pub fn build_client(settings: &NetworkSettings) -> Result<reqwest::Client, Box<dyn Error>> {
+    let mut builder = reqwest::Client::builder()
+        .user_agent(format!("aeco-launcher/{}", version_summary()))
+        .connect_timeout(Duration::from_secs(settings.connect_timeout));
+
+    builder = match settings.proxy_mode {
+        // reqwest already picks up the system and environment proxies
+        ProxyMode::System => builder,
+        ProxyMode::None => builder.no_proxy(),
+        ProxyMode::Manual => {
+            let mut proxy = Proxy::all(settings.proxy_url.as_str())?;
+
+            if !settings.proxy_username.is_empty() {
+                proxy = proxy.basic_auth(&settings.proxy_username, &settings.proxy_password);
+            }
+
+            let no_proxy = if settings.no_proxy.is_empty() {
+                NoProxy::from_env()
+            } else {
+                NoProxy::from_string(&settings.no_proxy)
+            };
+
+            builder.proxy(proxy.no_proxy(no_proxy))
+        }
+    };
+
+    Ok(builder.build()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{content_range, is_resumed, response_validator};
+    use reqwest::header::{HeaderMap, HeaderName, CONTENT_RANGE, ETAG, LAST_MODIFIED};
+    use reqwest::StatusCode;
+
+    fn headers(values: &[(HeaderName, &str)]) -> HeaderMap {
+        values
+            .iter()
+            .map(|(name, value)| (name.clone(), value.parse().unwrap()))
+            .collect()
+    }
+
+    fn range(value: &str) -> Option<(u64, Option<u64>)> {
+        content_range(&headers(&[(CONTENT_RANGE, value)]))
+    }
+
+    #[test]
+    fn reads_content_range() {
+        assert_eq!(range("bytes 100-199/1000"), Some((100, Some(1000))));
+        assert_eq!(range("bytes 0-0/1"), Some((0, Some(1))));
+        // The size may be unknown
+        assert_eq!(range("bytes 100-199/*"), Some((100, None)));
+    }
+
+    #[test]
+    fn reads_unsatisfiable_range() {
+        assert_eq!(range("bytes */1000"), Some((0, Some(1000))));
+        assert_eq!(range("bytes */x"), Some((0, None)));
+    }
+
+    #[test]
+    fn rejects_malformed_content_range() {
+        assert_eq!(content_range(&HeaderMap::new()), None);
+        assert_eq!(range("items 100-199/1000"), None);
+        assert_eq!(range("bytes 100-199"), None);
+        assert_eq!(range("bytes 100/1000"), None);
+        assert_eq!(range("bytes x-199/1000"), None);
+        assert_eq!(range("bytes -5-199/1000"), None);
+    }
+
+    #[test]
+    fn only_resumes_partial_content_at_the_offset() {
+        let partial = headers(&[(CONTENT_RANGE, "bytes 100-999/1000")]);
+        assert_eq!(
+            is_resumed(StatusCode::PARTIAL_CONTENT, &partial, 100),
+            Ok(true)
+        );
+
+        // The whole file is never appended to the partial download
+        assert_eq!(is_resumed(StatusCode::OK, &partial, 100), Ok(false));
+        assert_eq!(
+            is_resumed(StatusCode::OK, &HeaderMap::new(), 100),
+            Ok(false)
+        );
+
+        assert!(is_resumed(StatusCode::PARTIAL_CONTENT, &partial, 50).is_err());
+        assert!(is_resumed(StatusCode::PARTIAL_CONTENT, &HeaderMap::new(), 100).is_err());
+        let malformed = headers(&[(CONTENT_RANGE, "bytes 100/1000")]);
+        assert!(is_resumed(StatusCode::PARTIAL_CONTENT, &malformed, 100).is_err());
+    }
+
+    #[test]
+    fn picks_validator() {
+        let modified = "Wed, 21 Oct 2026 07:28:00 GMT";
+        let strong = headers(&[(ETAG, "\"abc\""), (LAST_MODIFIED, modified)]);
+        assert_eq!(response_validator(&strong).as_deref(), Some("\"abc\""));
+
+        // Weak ETags can't be used with If-Range
+        let weak = headers(&[(ETAG, "W/\"abc\""), (LAST_MODIFIED, modified)]);
+        assert_eq!(response_validator(&weak).as_deref(), Some(modified));
+        let weak_only = headers(&[(ETAG, "W/\"abc\"")]);
+        assert_eq!(response_validator(&weak_only), None);
+
+        assert_eq!(response_validator(&HeaderMap::new()), None);
+    }
+}
@@ -0,0 +1,73 @@
+mod file;
+mod http;
+pub use file::FileTransport;
+pub use http::{build_client, HttpTransport};
+
+use crate::settings::NetworkSettings;
+use reqwest::Url;
+use std::error::Error;
+
+/// Somewhere patches can be downloaded from, such as a web server or a local
+/// copy of the patch server on a USB drive or network share
+pub trait Transport: Send {
+    /// Starts fetching the file at `url`, skipping the first `offset` bytes if
+    /// the transport supports it and the file is still the one described by
+    /// `validator`, from an earlier download
+    fn fetch(
+        &self,
+        url: &Url,
+        offset: u64,
+        validator: Option<&str>,
+    ) -> Result<Download, Box<dyn Error>>;
+}
+
+/// A file which is being fetched by a transport
+pub struct Download {
+    /// Whether the data starts at the requested offset. If not, the whole file
+    /// is being sent from the beginning.
+    pub resumed: bool,
+    /// How many bytes are left to be sent, if known
+    pub remaining_size: Option<u64>,
+    /// Identifies this version of the file, such as an HTTP ETag, so a later
+    /// download can check that it is continuing the same file
+    pub validator: Option<String>,
+    pub body: Box<dyn Body>,
+}
+
+/// The contents of a file which is being fetched by a transport
+pub trait Body {
+    /// Gets the next chunk of the file, or `None` when the file is complete
+    fn next_chunk(&mut self) -> Result<Option<Vec<u8>>, Box<dyn Error>>;
+}
+
+impl Download {
+    /// A download which has nothing left to send
+    fn finished(validator: Option<String>) -> Self {
+        Self {
+            resumed: true,
+            remaining_size: Some(0),
+            validator,
+            body: Box::new(EmptyBody),
+        }
+    }
+}
+
+struct EmptyBody;
+
+impl Body for EmptyBody {
+    fn next_chunk(&mut self) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+        Ok(None)
+    }
+}
+
+/// Picks a transport based on the scheme of the patch server URL
+pub fn for_url(
+    url: &Url,
+    settings: &NetworkSettings,
+) -> Result<Box<dyn Transport>, Box<dyn Error>> {
+    match url.scheme() {
+        "http" | "https" => Ok(Box::new(HttpTransport::new(settings)?)),
+        "file" => Ok(Box::new(FileTransport)),
+        scheme => Err(format!("Unsupported patch server URL scheme '{scheme}'").into()),
+    }
+}
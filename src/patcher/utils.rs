@@ -1,3 +1,4 @@
+use std::ffi::OsString;
 use std::path::Path;
 
 /// Format a quantity of bytes into a human readable string
@@ -18,6 +19,34 @@ pub fn get_platform() -> String {
     format!("{os}-{arch}")
 }
 
+/// Gets the environment for a new process: the launcher's environment with
+/// `env` added on top. Returns `None` if nothing is added, so the process just
+/// inherits the launcher's environment.
+pub fn process_env(env: &[(OsString, OsString)]) -> Option<Vec<(OsString, OsString)>> {
+    if env.is_empty() {
+        return None;
+    }
+
+    let mut full_env = subprocess::PopenConfig::current_env();
+    full_env.retain(|(key, _)| !env.iter().any(|(new_key, _)| new_key == key));
+    full_env.extend_from_slice(env);
+    Some(full_env)
+}
+
+/// Turns a name chosen by the player into something which can be used in a
+/// file name on every platform
+pub fn file_name_safe(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
 /// Sets a file to be executable on Unixlikes, or does nothing on Windows
 pub fn set_executable<P>(path: P) -> std::io::Result<()>
 where
@@ -30,3 +59,132 @@ where
     }
     Ok(())
 }
+
+/// Device names which Windows reserves in every directory, regardless of any
+/// extension that follows them
+const RESERVED_WINDOWS_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Checks that a name from the patch info is a single, plain file or directory
+/// name, so that joining it onto a directory cannot lead outside of it.
+/// Returns the reason the name was rejected otherwise.
+pub fn check_entry_name(name: &str) -> Result<(), &'static str> {
+    if name.is_empty() {
+        return Err("the name is empty");
+    }
+
+    if name.contains('\0') {
+        return Err("the name contains a NUL character");
+    }
+
+    if name.contains(['/', '\\']) {
+        return Err("the name contains a path separator");
+    }
+
+    if name == "." || name == ".." {
+        return Err("the name refers to a directory by itself");
+    }
+
+    // This also catches drive prefixes such as "C:"
+    if name.contains(':') {
+        return Err("the name contains a colon");
+    }
+
+    // Windows strips these, which can turn e.g. ". ." into ".."
+    if name.ends_with(['.', ' ']) {
+        return Err("the name ends with a dot or a space");
+    }
+
+    let stem = name.split('.').next().unwrap_or(name).trim_end();
+    if RESERVED_WINDOWS_NAMES
+        .iter()
+        .any(|reserved| stem.eq_ignore_ascii_case(reserved))
+    {
+        return Err("the name is reserved on Windows");
+    }
+
+    Ok(())
+}
+
+/// Format a number of seconds as a rough human readable duration, such as
+/// `3 days` or `1 hour`
+pub fn duration_string(seconds: u64) -> String {
+    let (amount, unit) = match seconds {
+        s if s >= 86400 => (s / 86400, "day"),
+        s if s >= 3600 => (s / 3600, "hour"),
+        s if s >= 60 => (s / 60, "minute"),
+        s => (s, "second"),
+    };
+
+    if amount == 1 {
+        format!("{amount} {unit}")
+    } else {
+        format!("{amount} {unit}s")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check_entry_name;
+
+    #[test]
+    fn accepts_plain_names() {
+        assert_eq!(check_entry_name("eco.exe"), Ok(()));
+        assert_eq!(check_entry_name(".hidden"), Ok(()));
+        assert_eq!(check_entry_name("a b.txt"), Ok(()));
+        assert_eq!(check_entry_name("CONSOLE.txt"), Ok(()));
+    }
+
+    #[test]
+    fn rejects_directory_references() {
+        let reason = Err("the name refers to a directory by itself");
+        assert_eq!(check_entry_name("."), reason);
+        assert_eq!(check_entry_name(".."), reason);
+        assert_eq!(check_entry_name(""), Err("the name is empty"));
+    }
+
+    #[test]
+    fn rejects_paths() {
+        let reason = Err("the name contains a path separator");
+        assert_eq!(check_entry_name("../eco.exe"), reason);
+        assert_eq!(check_entry_name("/etc"), reason);
+        assert_eq!(check_entry_name("a\\b"), reason);
+        assert_eq!(check_entry_name("a/b"), reason);
+    }
+
+    #[test]
+    fn rejects_drives_and_urls() {
+        let reason = Err("the name contains a colon");
+        assert_eq!(check_entry_name("C:x"), reason);
+        assert_eq!(check_entry_name("C:"), reason);
+        assert_eq!(check_entry_name("eco.exe:stream"), reason);
+        assert!(check_entry_name("https://example.com").is_err());
+    }
+
+    #[test]
+    fn rejects_names_windows_changes() {
+        let reason = Err("the name ends with a dot or a space");
+        assert_eq!(check_entry_name(". ."), reason);
+        assert_eq!(check_entry_name("eco.exe."), reason);
+        assert_eq!(check_entry_name("eco.exe "), reason);
+    }
+
+    #[test]
+    fn rejects_reserved_windows_names() {
+        let reason = Err("the name is reserved on Windows");
+        assert_eq!(check_entry_name("CON"), reason);
+        assert_eq!(check_entry_name("CON.txt"), reason);
+        assert_eq!(check_entry_name("con .txt"), reason);
+        assert_eq!(check_entry_name("lpt1.dat"), reason);
+    }
+
+    #[test]
+    fn rejects_nul() {
+        assert_eq!(
+            check_entry_name("a\0b"),
+            Err("the name contains a NUL character")
+        );
+    }
+}
@@ -0,0 +1,179 @@
+use super::constants::{WINE_PREFIX_DIR, WINE_PREFIX_MARKER};
+use crate::runner::Runner;
+use std::error::Error;
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+use subprocess::{Exec, ExitStatus, Redirection};
+
+/// Bumped whenever the prefix setup changes, so existing prefixes get the new
+/// setup the next time the game starts
+const PREFIX_VERSION: u32 = 1;
+
+/// Fonts the game asks for, which Wine does not have
+const JAPANESE_FONTS: &[&str] = &[
+    "MS Gothic",
+    "MS PGothic",
+    "MS UI Gothic",
+    "MS Mincho",
+    "MS PMincho",
+    "\u{FF2D}\u{FF33} \u{30B4}\u{30B7}\u{30C3}\u{30AF}",
+    "\u{FF2D}\u{FF33} \u{FF30}\u{30B4}\u{30B7}\u{30C3}\u{30AF}",
+    "\u{FF2D}\u{FF33} \u{660E}\u{671D}",
+    "\u{FF2D}\u{FF33} \u{FF30}\u{660E}\u{671D}",
+];
+
+/// The Wine prefix used for the game, which is kept in the game directory so
+/// that other Windows programs can't break it
+pub fn prefix_path(game_dir: &Path) -> PathBuf {
+    game_dir.join(WINE_PREFIX_DIR)
+}
+
+/// Environment variables which make Wine use the game's prefix
+pub fn prefix_env(game_dir: &Path) -> Vec<(OsString, OsString)> {
+    vec![(
+        OsString::from("WINEPREFIX"),
+        prefix_path(game_dir).into_os_string(),
+    )]
+}
+
+/// Whether the prefix has been created and set up for the current version of
+/// the launcher
+pub fn is_prefix_ready(game_dir: &Path) -> bool {
+    let marker = prefix_path(game_dir).join(WINE_PREFIX_MARKER);
+    match std::fs::read_to_string(marker) {
+        Ok(version) => version.trim() == PREFIX_VERSION.to_string(),
+        Err(_) => false,
+    }
+}
+
+/// Creates the prefix if needed and sets it up for the game. Returns a warning
+/// for the player if the game might not display properly.
+pub fn prepare_prefix(game_dir: &Path, runner: &Runner) -> Result<Option<String>, Box<dyn Error>> {
+    let prefix = prefix_path(game_dir);
+    let mut env = prefix_env(game_dir);
+
+    // Without this, Wine adds menu entries and file associations for the
+    // prefix to the desktop while setting it up
+    env.push((
+        OsString::from("WINEDLLOVERRIDES"),
+        OsString::from("winemenubuilder.exe=d"),
+    ));
+
+    std::fs::create_dir_all(&prefix)?;
+    run_wine(runner, &["wineboot", "--init"], &env)?;
+
+    let japanese_font = find_japanese_font();
+    let reg_path = prefix.join("aeco-launcher.reg");
+    std::fs::write(&reg_path, registry_file(japanese_font.as_deref()))?;
+    let reg_result = run_wine(
+        runner,
+        &[
+            OsStr::new("regedit"),
+            OsStr::new("/S"),
+            reg_path.as_os_str(),
+        ],
+        &env,
+    );
+    std::fs::remove_file(&reg_path).ok();
+    reg_result?;
+
+    // Let Wine finish writing the registry before anything else uses it
+    Exec::cmd(runner.wineserver())
+        .arg("-w")
+        .env_extend(&env)
+        .join()
+        .ok();
+
+    std::fs::write(prefix.join(WINE_PREFIX_MARKER), PREFIX_VERSION.to_string())?;
+
+    Ok(match japanese_font {
+        Some(_) => None,
+        None => Some(
+            "No Japanese font was found, so some text in the game may not display. \
+             Installing a font such as Noto Sans CJK JP should fix this."
+                .to_string(),
+        ),
+    })
+}
+
+/// Deletes the prefix, so it is created from scratch the next time the game
+/// starts
+pub fn reset_prefix(game_dir: &Path) -> Result<(), Box<dyn Error>> {
+    let prefix = prefix_path(game_dir);
+    if prefix.exists() {
+        std::fs::remove_dir_all(prefix)?;
+    }
+    Ok(())
+}
+
+/// Runs a Wine command and waits for it to finish
+fn run_wine(
+    runner: &Runner,
+    args: &[impl AsRef<OsStr>],
+    env: &[(OsString, OsString)],
+) -> Result<(), Box<dyn Error>> {
+    let capture = Exec::cmd(&runner.wine)
+        .args(args)
+        .env_extend(env)
+        .stdout(Redirection::Pipe)
+        .stderr(Redirection::Merge)
+        .capture()?;
+    match capture.exit_status {
+        ExitStatus::Exited(0) => Ok(()),
+        status => {
+            log::warn!("Wine output:\n{}", capture.stdout_str());
+            Err(format!("Wine exited with {status:?}").into())
+        }
+    }
+}
+
+/// Finds an installed font which can display Japanese, using fontconfig
+pub fn find_japanese_font() -> Option<String> {
+    let capture = Exec::cmd("fc-match")
+        .args(&["--format=%{family[0]}", ":lang=ja"])
+        .stdout(Redirection::Pipe)
+        .capture()
+        .ok()?;
+
+    if !capture.success() {
+        return None;
+    }
+
+    // fontconfig always matches something, but only some fonts cover Japanese
+    let family = capture.stdout_str().trim().to_string();
+    let capture = Exec::cmd("fc-list")
+        .args(&[format!("{family}:lang=ja"), "family".to_string()])
+        .stdout(Redirection::Pipe)
+        .capture()
+        .ok()?;
+
+    if capture.stdout_str().trim().is_empty() {
+        None
+    } else {
+        Some(family)
+    }
+}
+
+/// Creates the registry changes for the prefix, as a UTF-16 .reg file
+fn registry_file(japanese_font: Option<&str>) -> Vec<u8> {
+    let mut reg = String::from("Windows Registry Editor Version 5.00\r\n\r\n");
+
+    // Don't create menu entries and file associations
+    reg.push_str("[HKEY_CURRENT_USER\\Software\\Wine\\DllOverrides]\r\n");
+    reg.push_str("\"winemenubuilder.exe\"=\"\"\r\n\r\n");
+
+    if let Some(font) = japanese_font {
+        let font = font.replace('\\', "\\\\").replace('"', "\\\"");
+        reg.push_str("[HKEY_CURRENT_USER\\Software\\Wine\\Fonts\\Replacements]\r\n");
+        for name in JAPANESE_FONTS {
+            reg.push_str(&format!("\"{name}\"=\"{font}\"\r\n"));
+        }
+        reg.push_str("\r\n");
+    }
+
+    let mut bytes = vec![0xFF, 0xFE];
+    for unit in reg.encode_utf16() {
+        bytes.extend(unit.to_le_bytes());
+    }
+    bytes
+}
@@ -1,22 +1,62 @@
-use super::check_patches::check_platform_patches;
+use super::auth::{self, LoginError, Session};
+use super::branding;
+use super::bundle;
+use super::check_patches::{check_platform_patches, subdir_by_name, validate_patch_names};
 use super::constants::*;
+use super::diagnostics;
 use super::download;
-use super::error::{PatchError, PatchErrorLevel, ToPatchError};
-use super::utils::set_executable;
-use super::utils::{byte_string, get_platform};
-use crate::message::{GUIMessage, PatchMessage, PatchStatus};
-use aeco_patch_config::fsobject::*;
+use super::error::{
+    error_chain, CorruptArchive, ErrorCode, Interruption, PatchError, PatchErrorLevel,
+    ToPatchError, UntrustedUpdate,
+};
+use super::game::{self, GameExit, GameProcess, LaunchCommand};
+use super::game_ini::{self, Repair};
+use super::news;
+use super::signature;
+use super::state::{self, LauncherState};
+use super::status::StatusDocument;
+use super::transport::{self, Transport};
+use super::utils::{byte_string, duration_string, get_platform, process_env};
+use super::utils::{file_name_safe, set_executable};
+#[cfg(unix)]
+use super::wine;
+use crate::branding::Branding;
+use crate::credentials;
+use crate::ini::IniChange;
+use crate::message::{
+    CheckStatus, ErrorDetails, GUIMessage, GameCrash, PatchMessage, PatchStatus, ServerNotice,
+};
+use crate::news::News;
+use crate::runner::{self, Runner};
+use crate::settings::{AfterLaunch, ClientSettings, NetworkSettings, ProxyMode, Settings};
+use crate::version::{compare_versions, pkg_version};
 use aeco_patch_config::status::ServerStatus;
 use std::error::Error;
-use std::ffi::OsStr;
+use std::ffi::{OsStr, OsString};
+use std::io::{Read, Write};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::{
     path::PathBuf,
-    sync::mpsc::{Receiver, Sender},
+    sync::mpsc::{Receiver, RecvTimeoutError, Sender, TryRecvError},
 };
 use subprocess::PopenError;
 
 const UPDATE_FILE_EXTENSION: &str = "aecoupdate";
 
+/// How often the running game is checked on
+const GAME_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long a game client gets to read eco.ini before it is replaced for the
+/// next client
+const CLIENT_START_DELAY: Duration = Duration::from_secs(5);
+
+/// A game or client which starts once `at` is reached
+struct PendingLaunch {
+    at: Instant,
+    session: Option<Session>,
+    client: Option<ClientSettings>,
+}
+
 /// This is used for functions which need to communicate whether the program
 /// should continue running or shut down after executing.
 pub enum RunState {
@@ -24,20 +64,48 @@ pub enum RunState {
     Close,
 }
 
+/// A replacement for this launcher which was downloaded while patching
+pub struct UpdatedPatcher {
+    pub path: PathBuf,
+    /// The detached signature the update was verified with when downloaded.
+    /// This is `None` if signature checks are disabled.
+    pub signature: Option<Vec<u8>>,
+}
+
 pub struct PatchWorker {
     tx: Sender<PatchMessage>,
     rx: Receiver<GUIMessage>,
     pub self_dir: PathBuf,
     pub self_exe: PathBuf,
-    pub client: reqwest::Client,
+    pub transport: Box<dyn Transport>,
     pub server_url: reqwest::Url,
     pub game_base_url: reqwest::Url,
     pub game_zip_url: reqwest::Url,
     pub patchlist_url: reqwest::Url,
     pub status_url: reqwest::Url,
+    pub news_url: reqwest::Url,
+    pub branding_url: reqwest::Url,
     pub patch_url: reqwest::Url,
-    pub runtime: tokio::runtime::Runtime,
-    pub updated_patcher: Option<PathBuf>,
+    pub updated_patcher: Option<UpdatedPatcher>,
+    pub settings: Settings,
+    pub state: LauncherState,
+    /// Runners found on this computer, best first
+    pub runners: Vec<Runner>,
+    /// News items which had already been seen before this launcher started
+    pub seen_news_at_launch: Vec<String>,
+    /// Games started by this launcher, while they are being watched
+    games: Vec<GameProcess>,
+    /// The launcher window was hidden while the game runs
+    hidden: bool,
+    /// When a game was last started
+    last_launch: Option<Instant>,
+    /// A game waiting for the one started before it to read eco.ini
+    pending_launch: Option<PendingLaunch>,
+    /// Whether the game can only be played offline
+    offline: bool,
+    /// Why the patch server or network settings couldn't be used, if the
+    /// defaults are used instead
+    settings_problem: Option<String>,
 }
 
 impl PatchWorker {
@@ -51,40 +119,74 @@ impl PatchWorker {
             .ok_or_else(|| "No parent directory for the launcher was found.".to_string())?
             .to_path_buf();
 
-        let server_url = reqwest::Url::parse(PATCH_SERVER)?;
+        // A broken settings file should not stop the launcher from working
+        let settings = Settings::load(&self_dir).unwrap_or_else(|why| {
+            log::warn!("Could not load settings, using defaults: {why}");
+            Settings::default()
+        });
+
+        // Broken patch server or network settings fall back to the default
+        // server without a proxy, so that the player can still patch
+        let (server_url, transport, settings_problem) = match server_transport(&settings) {
+            Ok((server_url, transport)) => (server_url, transport, None),
+            Err(why) => {
+                log::error!(
+                    "{} Invalid patch server or network settings, using the defaults: {why}",
+                    ErrorCode::Settings.code()
+                );
+                let network = NetworkSettings {
+                    proxy_mode: ProxyMode::None,
+                    ..NetworkSettings::default()
+                };
+                let server_url = patch_server_url(&Settings::default())?;
+                let transport = transport::for_url(&server_url, &network)?;
+                (server_url, transport, Some(why.to_string()))
+            }
+        };
         let game_base_url = server_url.join(BASE_DIR)?;
         let game_zip_url = game_base_url.join(BASE_ZIP)?;
         let meta_url = server_url.join(META_DIR)?;
         let patchlist_url = meta_url.join(PATCHLIST)?;
         let status_url = meta_url.join(STATUS)?;
+        let news_url = meta_url.join(NEWS)?;
+        let branding_url = meta_url.join(BRANDING)?;
         let patch_url = server_url.join(PATCH_DIR)?;
 
-        let client = reqwest::Client::builder().build()?;
-        let runtime = tokio::runtime::Builder::new_multi_thread()
-            .enable_all()
-            .build()?;
+        let state = LauncherState::load(&self_dir);
+        let seen_news_at_launch = state.seen_news.clone();
 
         Ok(Self {
             tx: sender,
             rx: receiver,
             self_dir,
             self_exe,
-            client,
+            transport,
             server_url,
             game_base_url,
             game_zip_url,
             patchlist_url,
             status_url,
+            news_url,
+            branding_url,
             patch_url,
-            runtime,
             updated_patcher: None,
+            settings,
+            state,
+            runners: Vec::new(),
+            seen_news_at_launch,
+            games: Vec::new(),
+            hidden: false,
+            last_launch: None,
+            pending_launch: None,
+            offline: false,
+            settings_problem,
         })
     }
 
     /// Send a message to the GUI
     fn send(&self, message: PatchMessage) {
         if let Err(why) = self.tx.send(message) {
-            eprintln!("Could not send message from PatchWorker to GUI: {why}");
+            log::error!("Could not send message from PatchWorker to GUI: {why}");
         }
     }
 
@@ -94,6 +196,21 @@ impl PatchWorker {
         self.send(PatchMessage::Error(text));
     }
 
+    /// Send an error to the GUI, with details which the player can pass on to
+    /// support
+    pub fn send_error_details(&self, code: ErrorCode, text: String, why: &dyn Error) {
+        self.send_error(text.clone());
+        self.send(PatchMessage::ErrorDetails(ErrorDetails {
+            code: code.code().to_string(),
+            message: text,
+            causes: error_chain(why)
+                .iter()
+                .map(|cause| bundle::redact_text(cause))
+                .collect(),
+            remedy: code.remedy().to_string(),
+        }));
+    }
+
     /// Send download information to the GUI
     pub fn send_download(&self, text: String, percentage: f32) {
         self.send(PatchMessage::Downloading(text, percentage));
@@ -104,6 +221,21 @@ impl PatchWorker {
         self.send(PatchMessage::Info(text));
     }
 
+    /// Send the news to the GUI
+    pub fn send_news(&self, news: News) {
+        self.send(PatchMessage::News(news));
+    }
+
+    /// Send the link buttons and background to the GUI
+    pub fn send_branding(&self, branding: Branding) {
+        self.send(PatchMessage::Branding(branding));
+    }
+
+    /// Show or hide the warning banner in the GUI
+    pub fn send_warning(&self, text: Option<String>) {
+        self.send(PatchMessage::Warning(text));
+    }
+
     /// Send information about the result of the patch routine to the GUI
     pub fn send_status(&self, status: PatchStatus) {
         self.send(PatchMessage::PatchStatus(status));
@@ -114,11 +246,73 @@ impl PatchWorker {
         self.rx.recv()
     }
 
+    /// Drops the messages which were sent before the status changed. The
+    /// player still expects their login to be forgotten, though.
     fn clear_recv(&self) {
-        while self.rx.try_recv().is_ok() {}
+        while let Ok(message) = self.rx.try_recv() {
+            if let GUIMessage::ForgetLogin = message {
+                forget_login();
+            }
+        }
+    }
+
+    /// Handles pause, resume and cancel requests from the GUI. This should be
+    /// called by long-running operations at points where they can safely be
+    /// stopped, such as between downloaded chunks or extracted files.
+    ///
+    /// While paused, this blocks until the GUI asks to resume or cancel.
+    pub fn check_interruption(&self) -> Result<(), Interruption> {
+        let mut paused = false;
+        loop {
+            let message = if paused {
+                self.recv().map_err(|_| Interruption::Closed)?
+            } else {
+                match self.rx.try_recv() {
+                    Ok(message) => message,
+                    Err(TryRecvError::Empty) => return Ok(()),
+                    Err(TryRecvError::Disconnected) => return Err(Interruption::Closed),
+                }
+            };
+
+            match message {
+                GUIMessage::Pause => {
+                    if !paused {
+                        paused = true;
+                        self.send(PatchMessage::PatchStatus(PatchStatus::Paused));
+                    }
+                }
+                GUIMessage::Resume => {
+                    if paused {
+                        paused = false;
+                        self.send(PatchMessage::PatchStatus(PatchStatus::Working));
+                    }
+                }
+                GUIMessage::Cancel => return Err(Interruption::Cancelled),
+                GUIMessage::Close => return Err(Interruption::Closed),
+                GUIMessage::ForgetLogin => forget_login(),
+                // Nothing else can be done until the current operation ends
+                GUIMessage::Retry
+                | GUIMessage::Play
+                | GUIMessage::Login { .. }
+                | GUIMessage::StartClient { .. }
+                | GUIMessage::SaveSettings(_)
+                | GUIMessage::SaveGameIni(_)
+                | GUIMessage::RestoreGameIni(_)
+                | GUIMessage::SaveIniProfile(_)
+                | GUIMessage::DeleteIniProfile(_)
+                | GUIMessage::RunDiagnostics
+                | GUIMessage::ExportDiagnostics
+                | GUIMessage::ResetWinePrefix => {}
+            }
+        }
     }
 
     pub fn run(mut self) {
+        // Let the settings window show the current settings
+        self.send(PatchMessage::Settings(Box::new(self.settings.clone())));
+        self.find_runners();
+        self.send_saved_logins();
+
         // Main loop includes all message handling and patching operations
         self.main_loop();
         // Since this takes ownership, once this ends, drop() will be called
@@ -130,6 +324,10 @@ impl PatchWorker {
         let mut message = GUIMessage::Retry;
         loop {
             match message {
+                // Patching would replace the files of the running game
+                GUIMessage::Retry if !self.games.is_empty() || self.pending_launch.is_some() => {
+                    self.send_info("Close the game before patching again".to_string());
+                }
                 GUIMessage::Retry => {
                     self.send_status(PatchStatus::Working);
                     match self.patch_routine() {
@@ -140,46 +338,106 @@ impl PatchWorker {
                         Ok(RunState::Close) => return,
 
                         Err(why) => {
-                            // Communicate error status to the GUI
-                            self.send_status(PatchStatus::Error);
-
-                            // Display error message
-                            match why.level {
-                                PatchErrorLevel::Low => self.send_info(why.friendly_message),
-                                PatchErrorLevel::High => self.send_error(why.friendly_message),
+                            match why.internal_error.downcast_ref::<Interruption>() {
+                                // The GUI is gone, so there is nothing left to do
+                                Some(Interruption::Closed) => return,
+
+                                // Let the user start over with RETRY
+                                Some(Interruption::Cancelled) => {
+                                    self.send_status(PatchStatus::Error);
+                                    self.send_info("Patching was cancelled".to_string());
+                                }
+
+                                None => {
+                                    // Communicate error status to the GUI
+                                    self.send_status(PatchStatus::Error);
+
+                                    // Keep the detailed error for support
+                                    log::error!(
+                                        "{} {}: {:?}",
+                                        why.code.code(),
+                                        why.friendly_message,
+                                        why.internal_error
+                                    );
+
+                                    // Display error message
+                                    match why.level {
+                                        PatchErrorLevel::Low => {
+                                            self.send_info(why.friendly_message)
+                                        }
+                                        PatchErrorLevel::High => self.send_error_details(
+                                            why.code,
+                                            why.friendly_message,
+                                            why.internal_error.as_ref(),
+                                        ),
+                                    }
+                                }
                             }
-
-                            // Log more detailed error info to the terminal
-                            eprintln!("{:?}", why.internal_error);
                         }
                     }
+
+                    // eco.ini can be edited once patching is over, whether or
+                    // not it worked
+                    self.send_game_ini();
                 }
                 GUIMessage::Play => {
-                    self.send_download("Starting game...".to_string(), 1.);
-                    match self.start_game() {
-                        Ok(_) => {
-                            // The game is running and we can exit
-                            self.send_download("Game has started!".to_string(), 1.);
-                            std::thread::sleep(std::time::Duration::from_secs(3));
-                            return;
-                        }
-                        Err(why) => {
-                            // Could not launch the game, need to stay open to inform user
-                            self.send_status(PatchStatus::Error);
-                            self.send_error("Failed to launch the game".to_string());
-                            eprintln!("Failed to launch game: {why}");
-                        }
+                    if self.can_launch_game() {
+                        self.launch_game(None, None);
+                    }
+                }
+                GUIMessage::Login {
+                    username,
+                    password,
+                    remember,
+                } => {
+                    // Logging in is pointless if the game can't start anyway
+                    let session = if self.can_launch_game() {
+                        self.login(&username, &password, remember)
+                    } else {
+                        None
+                    };
+                    if let Some(session) = session {
+                        self.launch_game(Some(session), None);
                     }
                 }
+                GUIMessage::StartClient {
+                    name,
+                    password,
+                    remember,
+                } => self.start_client(&name, &password, remember),
+                GUIMessage::SaveSettings(settings) => self.save_settings(*settings),
+                GUIMessage::SaveGameIni(changes) => self.save_game_ini(&changes),
+                GUIMessage::RestoreGameIni(name) => self.restore_game_ini(&name),
+                GUIMessage::SaveIniProfile(profile) => self.save_ini_profile(&profile),
+                GUIMessage::DeleteIniProfile(profile) => self.delete_ini_profile(&profile),
+                GUIMessage::RunDiagnostics => {
+                    self.send(PatchMessage::Diagnostics(diagnostics::run_checks(self)));
+                }
+                GUIMessage::ExportDiagnostics => self.export_diagnostics(),
+                GUIMessage::ResetWinePrefix => self.reset_wine_prefix(),
+                GUIMessage::ForgetLogin => forget_login(),
+                // Cancel also stops a game which is waiting to start
+                GUIMessage::Cancel => self.cancel_pending_launch(),
+                // Pause and resume only apply while patching
+                GUIMessage::Pause | GUIMessage::Resume => {}
                 // Close if the GUI closes
                 GUIMessage::Close => return,
             }
 
-            message = match self.recv() {
-                Ok(m) => m,
-                Err(why) => {
-                    eprintln!("{why}");
-                    return;
+            // Keep an eye on the game while waiting for the GUI
+            message = loop {
+                match self.rx.recv_timeout(GAME_POLL_INTERVAL) {
+                    Ok(m) => break m,
+                    Err(RecvTimeoutError::Timeout) => {
+                        if let RunState::Close = self.watch_game() {
+                            return;
+                        }
+                        self.start_pending_launch();
+                    }
+                    Err(why) => {
+                        log::error!("The GUI has closed: {why}");
+                        return;
+                    }
                 }
             };
         }
@@ -190,18 +448,58 @@ impl PatchWorker {
             return Ok(RunState::Close);
         }
 
+        // Invalid settings stay visible until they are fixed
+        self.send_warning(self.settings_problem.as_ref().map(|why| {
+            format!(
+                "The patch server or network settings can't be used, so the default server \
+                 is used without a proxy: {why}"
+            )
+        }));
+        // Keep the player's settings in case a patch replaces eco.ini
+        self.backup_game_ini();
         self.send_info("Checking server status".to_string());
-        let server_status = download::server_status(self)?;
+        let server_status = match download::server_status(self) {
+            Ok(server_status) => server_status,
+            Err(why) => return self.offline_mode(why),
+        };
 
-        match server_status {
-            ServerStatus::Online => self.send_info("Server is online".to_string()),
-            ServerStatus::Maintenance => {
-                return Err(Box::<dyn Error>::from(format!(
-                    "Received server status {server_status:?}"
-                ))
-                .to_patch_error_level("Server is down for maintenance", PatchErrorLevel::Low));
-            }
+        self.send_server_notice(&server_status);
+
+        news::update_news(self, true)
+            .map_err(|why| why.to_patch_error("Stopped while getting news", ErrorCode::News))?;
+        branding::update_branding(self, true).map_err(|why| {
+            why.to_patch_error("Stopped while getting branding", ErrorCode::Branding)
+        })?;
+
+        // The server can refuse launchers which are too old, or require them
+        // to update themselves before doing anything else
+        let launcher_outdated = is_launcher_outdated(&server_status);
+        if launcher_outdated && !server_status.force_update {
+            return Err(Box::<dyn Error>::from(format!(
+                "Launcher version {:?} is older than the minimum version {:?}",
+                pkg_version(),
+                server_status.min_launcher_version
+            ))
+            .to_patch_error(
+                "This launcher is too old, please download the latest version",
+                ErrorCode::LauncherOutdated,
+            ));
+        }
+
+        // Keep checking until maintenance is over
+        let server_status = self.wait_for_maintenance(server_status)?;
+        if let ServerStatus::Maintenance = server_status.status {
+            return Err(Box::<dyn Error>::from(format!(
+                "Received server status {:?}",
+                server_status.status
+            ))
+            .to_patch_error_level(
+                "Server is down for maintenance",
+                ErrorCode::Maintenance,
+                PatchErrorLevel::Low,
+            ));
         }
+        self.send_info("Server is online".to_string());
 
         // Make sure the game is installed, and install it if not
         self.ensure_game_installed()?;
@@ -209,45 +507,264 @@ impl PatchWorker {
         // Get patch information from the patch server
         let patch = download::patch_metadata(self)?;
 
+        // Don't use patch information that could write outside of the game
+        // directory
+        validate_patch_names(&patch).map_err(|why| {
+            why.to_patch_error(
+                "Patch info contains an invalid file name",
+                ErrorCode::PatchInfoInvalid,
+            )
+        })?;
+
         // Apply patches for all platforms and for this specific platform
         for platform in ["all", &get_platform()] {
             // Compare local files against the patch data, and update files if needed
             if let Some(platform_dir) = subdir_by_name(&patch, platform) {
                 check_platform_patches(self, platform_dir).map_err(|why| {
-                    why.to_patch_error(&format!("Failed to check files for platform '{platform}'"))
+                    if why.is::<UntrustedUpdate>() {
+                        why.to_patch_error(
+                            "Launcher update is not trusted and was not installed",
+                            ErrorCode::UntrustedUpdate,
+                        )
+                    } else {
+                        why.to_patch_error(
+                            &format!("Failed to check files for platform '{platform}'"),
+                            ErrorCode::PatchFiles,
+                        )
+                    }
                 })?;
             } else {
-                println!("No patch directory found for platform \'{platform}\'");
+                log::info!("No patch directory found for platform \'{platform}\'");
             }
         }
 
+        // An outdated launcher was only allowed to continue so that it could
+        // replace itself
+        if launcher_outdated && self.updated_patcher.is_none() {
+            return Err(Box::<dyn Error>::from(
+                "The server requires a launcher update, but none was downloaded",
+            )
+            .to_patch_error(
+                "This launcher is too old, please download the latest version",
+                ErrorCode::LauncherOutdated,
+            ));
+        }
+
+        self.offline = false;
         self.send_status(PatchStatus::Finished);
 
         // Open the new patcher if there is one
-        if let Some(p) = &self.updated_patcher {
-            match start_detached_process(&[p]) {
+        if let Some(updated) = &self.updated_patcher {
+            self.verify_updated_patcher(updated)?;
+            match start_detached_process(&[&updated.path], &[]) {
                 // Close the patcher if the new patcher opened successfully
                 Ok(_) => return Ok(RunState::Close),
-                Err(why) => return Err(why.to_patch_error("Could not start updated launcher")),
+                Err(why) => {
+                    return Err(why.to_patch_error(
+                        "Could not start updated launcher",
+                        ErrorCode::LauncherUpdate,
+                    ))
+                }
+            }
+        }
+
+        self.check_eco_ini();
+
+        // Remember that the game is in a good state, so it can be played even
+        // if the patch server can't be reached next time
+        self.state.last_patched = Some(state::now());
+        self.save_state();
+
+        Ok(RunState::Continue)
+    }
+
+    /// Keeps checking the server status while the server is down for
+    /// maintenance, and returns the new status once it is back online.
+    /// If polling is turned off, the status is returned unchanged.
+    fn wait_for_maintenance(
+        &self,
+        mut server_status: StatusDocument,
+    ) -> Result<StatusDocument, PatchError> {
+        let polling = &self.settings.status_polling;
+        if polling.interval == 0 {
+            return Ok(server_status);
+        }
+
+        let mut interval = polling.interval;
+        let mut waited = false;
+
+        while let ServerStatus::Maintenance = server_status.status {
+            waited = true;
+
+            // Check soon after maintenance is supposed to end. Add a few
+            // seconds at random so players don't all reconnect at once.
+            let mut wait = interval;
+            if let Some(end) = server_status.maintenance.and_then(|m| m.end) {
+                let jitter = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|time| u64::from(time.subsec_millis()) % 15)
+                    .unwrap_or(0);
+                let until_end = end.saturating_sub(state::now());
+                if until_end > 0 {
+                    wait = wait.min(until_end + jitter);
+                }
+            }
+
+            self.maintenance_countdown(wait)?;
+            interval = (interval * 2)
+                .min(polling.max_interval)
+                .max(polling.interval);
+
+            server_status = match download::server_status(self) {
+                Ok(server_status) => server_status,
+                Err(why) if why.internal_error.is::<Interruption>() => return Err(why),
+                // The server may be unreliable during maintenance, so just
+                // try again later
+                Err(why) => {
+                    log::warn!(
+                        "Could not check server status during maintenance: {:?}",
+                        why.internal_error
+                    );
+                    continue;
+                }
+            };
+
+            self.send_server_notice(&server_status);
+        }
+
+        if waited && polling.notify_when_online {
+            self.send(PatchMessage::Notify("Server is back online!".to_string()));
+        }
+
+        Ok(server_status)
+    }
+
+    /// Waits for `seconds` while showing how long is left. Pausing and
+    /// cancelling still work while waiting.
+    fn maintenance_countdown(&self, seconds: u64) -> Result<(), PatchError> {
+        for remaining in (1..=seconds).rev() {
+            self.send_info(format!(
+                "Server is down for maintenance, checking again in {}:{:02}",
+                remaining / 60,
+                remaining % 60
+            ));
+
+            for _ in 0..4 {
+                self.check_interruption().map_err(|why| {
+                    why.to_patch_error("Stopped waiting for maintenance", ErrorCode::Maintenance)
+                })?;
+                std::thread::sleep(Duration::from_millis(250));
             }
         }
 
-        self.check_eco_ini()
-            .map_err(|why| why.to_patch_error("Failed while checking eco.ini"))?;
+        Ok(())
+    }
+
+    /// Passes on the parts of the server status which are meant for players
+    fn send_server_notice(&self, status: &StatusDocument) {
+        let to_time = |seconds: u64| UNIX_EPOCH + Duration::from_secs(seconds);
+        let maintenance = status.maintenance;
+
+        self.send(PatchMessage::ServerNotice(ServerNotice {
+            motd: status.motd.clone(),
+            maintenance_start: maintenance.and_then(|m| m.start).map(to_time),
+            maintenance_end: maintenance.and_then(|m| m.end).map(to_time),
+        }));
+    }
+
+    /// Called when the server status could not be retrieved. If the game was
+    /// completely patched at some point, it can still be played offline.
+    /// Otherwise, the original error is returned.
+    fn offline_mode(&mut self, why: PatchError) -> Result<RunState, PatchError> {
+        // Stopping the status check by hand does not mean the server is down
+        if why.internal_error.is::<Interruption>() {
+            return Err(why);
+        }
+
+        // Show the news and branding from last time, since the server can't be reached
+        news::update_news(self, false)
+            .map_err(|why| why.to_patch_error("Stopped while getting news", ErrorCode::News))?;
+        branding::update_branding(self, false).map_err(|why| {
+            why.to_patch_error("Stopped while getting branding", ErrorCode::Branding)
+        })?;
+
+        let last_patched = match self.state.last_patched {
+            Some(last_patched) if self.is_game_present() => last_patched,
+            _ => return Err(why),
+        };
+
+        log::warn!("Entering offline mode: {:?}", why.internal_error);
+
+        let age = duration_string(state::now().saturating_sub(last_patched));
+        self.send_warning(Some(format!(
+            "The patch server could not be reached. You can still play the game as it \
+             was last updated {age} ago, but it may be out of date."
+        )));
+        self.offline = true;
+        self.send_status(PatchStatus::Offline);
+        self.send_info("Offline mode".to_string());
 
         Ok(RunState::Continue)
     }
 
-    /// Checks whether the game is in the same directory as this program
+    /// Forgets that the game was completely patched, since game files are
+    /// about to change. Offline mode is not available until patching finishes.
+    pub fn mark_files_changed(&mut self) {
+        if self.state.last_patched.take().is_some() {
+            self.save_state();
+        }
+    }
+
+    pub fn save_state(&self) {
+        if let Err(why) = self.state.save(&self.self_dir) {
+            log::warn!("Could not save launcher state: {why}");
+        }
+    }
+
+    /// Checks the downloaded launcher update against its signature once more
+    /// right before it is executed, in case it was changed on disk since it
+    /// was downloaded. An update which fails this check is deleted.
+    fn verify_updated_patcher(&self, updated: &UpdatedPatcher) -> Result<(), PatchError> {
+        if !signature::CHECK_SIGNATURES {
+            return Ok(());
+        }
+
+        let result = match &updated.signature {
+            Some(signature_file) => std::fs::read(&updated.path)
+                .map_err(Box::<dyn Error>::from)
+                .and_then(|data| signature::verify(&data, signature_file)),
+            None => Err("No signature is known for the launcher update".into()),
+        };
+
+        result.map_err(|why| {
+            log::error!(
+                "Refusing to start launcher update {:?}: {why}",
+                updated.path
+            );
+            if let Err(why) = std::fs::remove_file(&updated.path) {
+                log::warn!("Could not remove untrusted launcher update: {why}");
+            }
+            UntrustedUpdate(why.to_string()).to_patch_error(
+                "Launcher update is not trusted and was not started",
+                ErrorCode::UntrustedUpdate,
+            )
+        })
+    }
+
+    /// Checks whether the game is in the same directory as this program.
+    /// A game whose extraction was interrupted is not considered present.
     fn is_game_present(&self) -> bool {
         let game_path = self.self_dir.join(GAME_EXE);
-        game_path.is_file()
+        let install_marker = self.self_dir.join(INSTALL_MARKER);
+        game_path.is_file() && !install_marker.exists()
     }
 
     /// Unpacks the base game ZIP to the same directory as this program
     fn unpack_base(&self, base_file: std::fs::File) -> Result<(), Box<dyn Error>> {
-        // Open base game archive
-        let mut archive = zip::read::ZipArchive::new(base_file)?;
+        // Open base game archive. Anything wrong with what is read from it
+        // means it has to be downloaded again.
+        let mut archive =
+            zip::read::ZipArchive::new(base_file).map_err(|why| CorruptArchive(why.to_string()))?;
 
         self.send_download("Extracting base game".to_string(), 0.);
 
@@ -258,17 +775,23 @@ impl PatchWorker {
         // Calculate the total number of bytes to be extracted
         let mut total_archive_bytes = 0;
         for file_number in 0..total_archive_count {
-            let file = archive.by_index(file_number)?;
+            let file = archive
+                .by_index(file_number)
+                .map_err(|why| CorruptArchive(why.to_string()))?;
             total_archive_bytes += file.size();
         }
 
         // Get total number of bytes as a human readable string
         let pretty_total = byte_string(total_archive_bytes);
+        log::info!("Extracting base game: {total_archive_count} files, {pretty_total}");
 
         // Keep track of how many bytes have been decompressed so far
         let mut decompressed_bytes = 0;
 
         for file_number in 0..total_archive_count {
+            // Stop between files if the user wants to pause or cancel
+            self.check_interruption()?;
+
             // Report progress in terms of bytes extracted
             let progress = decompressed_bytes as f32 / total_archive_bytes as f32;
             let pretty_decompressed = byte_string(decompressed_bytes);
@@ -282,11 +805,17 @@ impl PatchWorker {
             );
 
             // Get the next file from the archive
-            let mut file = archive.by_index(file_number)?;
+            let mut file = archive
+                .by_index(file_number)
+                .map_err(|why| CorruptArchive(why.to_string()))?;
 
             // Get its path and figure out where it should go on the system
-            let filepath = file.enclosed_name().ok_or("Invalid file path")?;
-            let outpath = self.self_dir.join(filepath);
+            let filepath = file
+                .enclosed_name()
+                .ok_or_else(|| CorruptArchive(format!("Invalid file path {:?}", file.name())))?
+                .to_path_buf();
+            let outpath = self.self_dir.join(&filepath);
+            log::debug!("Extracting {filepath:?}");
 
             if file.name().ends_with('/') {
                 // Create directories if needed
@@ -299,9 +828,20 @@ impl PatchWorker {
                     }
                 }
 
-                // Copy extracted file to disk
+                // Copy extracted file to disk. Reading fails if the data
+                // doesn't match its checksum, while writing can fail for
+                // reasons that have nothing to do with the archive.
                 let mut outfile = std::fs::File::create(&outpath)?;
-                std::io::copy(&mut file, &mut outfile)?;
+                let mut buffer = vec![0u8; 64 * 1024];
+                loop {
+                    let read = file
+                        .read(&mut buffer)
+                        .map_err(|why| CorruptArchive(format!("{filepath:?}: {why}")))?;
+                    if read == 0 {
+                        break;
+                    }
+                    outfile.write_all(&buffer[..read])?;
+                }
             }
 
             // Get and Set permissions
@@ -318,48 +858,668 @@ impl PatchWorker {
         }
 
         self.send_download("Finished installing base game".to_string(), 1.);
+        log::info!("Finished installing base game");
 
         Ok(())
     }
 
     /// Checks whether the game is installed and installs it if not
-    fn ensure_game_installed(&self) -> Result<(), PatchError> {
+    fn ensure_game_installed(&mut self) -> Result<(), PatchError> {
         self.send_download("Checking game installation".to_string(), 1.);
         if !self.is_game_present() {
-            self.send_download("Downloading game since it is not installed".to_string(), 0.);
+            self.mark_files_changed();
 
-            // Download the base game
-            let game_base_file = download::game_base(self)
-                .map_err(|why| why.to_patch_error("Failed while downloading base game"))?;
+            log::info!("The game is not installed, so the base game is downloaded");
+            self.send_download("Downloading game since it is not installed".to_string(), 0.);
 
-            // Extract the base game to disk
-            self.unpack_base(game_base_file)
-                .map_err(|why| why.to_patch_error("Failed while unpacking base game"))?;
+            // Download the base game. One which doesn't match its signed
+            // digest is removed, so that RETRY downloads it again.
+            let game_base_file = download::game_base(self).map_err(|why| {
+                if why.is::<CorruptArchive>() {
+                    log::warn!("Removing the base game archive, which failed verification");
+                    if let Err(why) = download::remove_game_base(self) {
+                        log::error!("Failed to remove the base game archive: {why}");
+                    }
+                }
+                why.to_patch_error(
+                    "Failed while downloading base game",
+                    ErrorCode::BaseGameDownload,
+                )
+            })?;
+
+            // Mark the installation as incomplete while extracting, so that
+            // the extraction is redone if it gets interrupted
+            let install_marker = self.self_dir.join(INSTALL_MARKER);
+            std::fs::write(&install_marker, []).map_err(|why| {
+                why.to_patch_error(
+                    "Failed while unpacking base game",
+                    ErrorCode::BaseGameExtract,
+                )
+            })?;
+
+            // Extract the base game to disk. A corrupt archive is removed, so
+            // that RETRY downloads it again instead of failing the same way.
+            self.unpack_base(game_base_file).map_err(|why| {
+                if why.is::<CorruptArchive>() {
+                    log::warn!("Removing the corrupt base game archive");
+                    if let Err(why) = download::remove_game_base(self) {
+                        log::error!("Failed to remove the corrupt base game archive: {why}");
+                    }
+                }
+                why.to_patch_error(
+                    "Failed while unpacking base game",
+                    ErrorCode::BaseGameExtract,
+                )
+            })?;
+
+            // The downloaded archive is not needed anymore
+            std::fs::remove_file(&install_marker).map_err(|why| {
+                why.to_patch_error(
+                    "Failed while unpacking base game",
+                    ErrorCode::BaseGameExtract,
+                )
+            })?;
+            download::remove_game_base(self).map_err(|why| {
+                why.to_patch_error(
+                    "Failed to remove base game archive",
+                    ErrorCode::BaseGameExtract,
+                )
+            })?;
         }
 
         Ok(())
     }
 
-    fn start_game(&self) -> Result<(), Box<dyn Error>> {
-        let game_full_path = self.self_dir.join(GAME_EXE);
-        let eco = OsStr::new(&game_full_path);
-        let launch = OsStr::new("/launch");
-        let wine = OsStr::new("wine");
-        let args = {
-            #[cfg(unix)]
-            {
-                // TODO: On Unixlike systems, perhaps a new wineprefix should be created
-                // TODO: On Unixlike systems, help the user install Wine
-                [wine, eco, launch].to_vec()
+    /// Logs in, and remembers the login if the player asked for it. Failures
+    /// are shown in the GUI.
+    fn login(&self, username: &str, password: &str, remember: bool) -> Option<Session> {
+        match auth::login(&self.settings, username, password) {
+            Ok(session) => {
+                let result = if remember {
+                    credentials::save(username, password)
+                } else {
+                    credentials::forget()
+                };
+                if let Err(why) = result {
+                    log::warn!("Could not update remembered login: {why}");
+                }
+                Some(session)
             }
-            #[cfg(windows)]
-            {
-                [eco, launch].to_vec()
+            Err(LoginError::Rejected(message)) => {
+                // A remembered login which stopped working is no use anymore
+                let saved = credentials::load();
+                if saved.is_some_and(|saved| saved.username == username) {
+                    if let Err(why) = credentials::forget() {
+                        log::warn!("Could not forget remembered login: {why}");
+                    }
+                    self.send(PatchMessage::LoginForgotten);
+                }
+                self.send(PatchMessage::LoginError(message));
+                None
+            }
+            Err(why) => {
+                self.send(PatchMessage::LoginError(
+                    "Could not reach the login server".to_string(),
+                ));
+                log::warn!("Could not reach the login server: {why}");
+                None
+            }
+        }
+    }
+
+    /// Checks whether the game can start. If not, the checks are shown in the
+    /// GUI so the player can fix the problems.
+    fn can_launch_game(&self) -> bool {
+        let checks = diagnostics::run_checks(self);
+        let failed = checks
+            .iter()
+            .filter(|check| check.status == CheckStatus::Failed)
+            .count();
+        if failed == 0 {
+            return true;
+        }
+
+        log::warn!("{failed} pre-launch checks failed");
+        let message = "The game can't start yet, see the setup checks".to_string();
+        // A running game keeps its status
+        if self.games.is_empty() {
+            self.send_error(message);
+        } else {
+            self.send_info(message);
+        }
+        self.send(PatchMessage::Diagnostics(checks));
+        false
+    }
+
+    /// Starts the game and tells the GUI how it went. The game is watched
+    /// from then on, so crashes can be reported. `client` is one of the extra
+    /// game clients from the settings, or `None` for the main game.
+    ///
+    /// A game with an eco.ini profile waits until the one started before it
+    /// has had time to read eco.ini. The worker keeps handling messages while
+    /// it waits.
+    fn launch_game(&mut self, session: Option<Session>, client: Option<ClientSettings>) {
+        let name = client.as_ref().map(|client| client.name.clone());
+        let what = match &name {
+            Some(name) => format!("client {name}"),
+            None => "game".to_string(),
+        };
+
+        if self.pending_launch.is_some() {
+            self.send_info(format!(
+                "The {what} can be started once the previous one has"
+            ));
+            return;
+        }
+        if let Some(wait) = self.profile_wait(client.as_ref()) {
+            log::info!("Waiting {wait:?} before starting the {what}");
+            self.send_download("Waiting for the previous client...".to_string(), 1.);
+            self.pending_launch = Some(PendingLaunch {
+                at: Instant::now() + wait,
+                session,
+                client,
+            });
+            return;
+        }
+
+        self.send_download(format!("Starting {what}..."), 1.);
+        match self.start_game(session.as_ref(), client.as_ref()) {
+            Ok(game) => {
+                log::info!("Started {what}, its output is in {:?}", game.log_path);
+                self.games.push(game);
+                if name.is_none() {
+                    self.send_status(PatchStatus::Playing);
+                }
+                self.send_running_clients();
+                self.send_download(format!("Started {what}!"), 1.);
+            }
+            Err(why) if name.is_some() => {
+                // The status is the main game's, which may be running
+                self.send_info(format!("Failed to launch the {what}"));
+                log::error!("Failed to launch {what}: {why}");
+            }
+            Err(why) => {
+                // Could not launch the game, need to stay open to inform user
+                self.send_status(PatchStatus::Error);
+                self.send_error_details(
+                    ErrorCode::GameLaunch,
+                    format!("Failed to launch the {what}"),
+                    why.as_ref(),
+                );
+                log::error!("Failed to launch {what}: {why}");
+            }
+        }
+    }
+
+    /// Logs in as a game client and starts it. With login turned off, the
+    /// client starts right away.
+    fn start_client(&mut self, name: &str, password: &str, remember: bool) {
+        let client = match self.settings.game.clients.iter().find(|c| c.name == name) {
+            Some(client) => client.clone(),
+            None => {
+                log::error!("Client {name:?} is not in the settings");
+                return;
+            }
+        };
+        if self
+            .games
+            .iter()
+            .any(|game| game.client.as_deref() == Some(name))
+        {
+            return;
+        }
+        if !self.can_launch_game() {
+            return;
+        }
+
+        if !self.settings.login.enabled {
+            self.launch_game(None, Some(client));
+            return;
+        }
+
+        match auth::login(&self.settings, &client.username, password) {
+            Ok(session) => {
+                let result = if remember {
+                    credentials::save_client(name, &client.username, password)
+                } else {
+                    credentials::forget_client(name)
+                };
+                if let Err(why) = result {
+                    log::warn!("Could not update remembered login of client {name:?}: {why}");
+                }
+                self.launch_game(Some(session), Some(client));
+            }
+            Err(LoginError::Rejected(message)) => {
+                self.send_info(format!("Client {name}: {message}"));
+            }
+            Err(why) => {
+                self.send_info(format!("Client {name}: Could not reach the login server"));
+                log::warn!("Client {name:?} could not reach the login server: {why}");
+            }
+        }
+    }
+
+    /// Tells the GUI which game clients are running
+    fn send_running_clients(&self) {
+        let running = self
+            .games
+            .iter()
+            .filter_map(|game| game.client.clone())
+            .collect();
+        self.send(PatchMessage::RunningClients(running));
+    }
+
+    /// Checks on the running games, and does what the settings ask for once
+    /// the main game has started or exited. Extra clients always keep the
+    /// launcher open, since it is their control window.
+    fn watch_game(&mut self) -> RunState {
+        let mut i = 0;
+        while i < self.games.len() {
+            let alone = self.games.len() == 1;
+            let game = &mut self.games[i];
+            let was_started = game.has_started();
+
+            let exit = match game.poll(alone) {
+                Ok(Some(exit)) => exit,
+                Ok(None) => {
+                    let just_started = !was_started && game.has_started();
+                    if just_started && game.client.is_none() && !self.has_clients_running() {
+                        match self.settings.game.after_launch {
+                            AfterLaunch::Close => return RunState::Close,
+                            AfterLaunch::Hide => {
+                                self.hidden = true;
+                                self.send(PatchMessage::HideWindow(true));
+                            }
+                            AfterLaunch::Stay => {}
+                        }
+                    }
+                    i += 1;
+                    continue;
+                }
+                Err(why) => {
+                    // The game itself may still be running, there is just no
+                    // way to tell anymore
+                    log::warn!("Stopped watching the game: {why}");
+                    GameExit::Normal
+                }
+            };
+
+            let game = self.games.remove(i);
+            if let RunState::Close = self.game_exited(game, exit) {
+                return RunState::Close;
+            }
+        }
+
+        RunState::Continue
+    }
+
+    /// Tells the player how a game ended
+    fn game_exited(&mut self, game: GameProcess, exit: GameExit) -> RunState {
+        self.send_running_clients();
+        // The game saves its options when it exits
+        self.backup_game_ini();
+        self.send_game_ini();
+        let what = match &game.client {
+            Some(name) => format!("Client {name}"),
+            None => "The game".to_string(),
+        };
+
+        match exit {
+            GameExit::Normal => {
+                let after_launch = self.settings.game.after_launch;
+                if self.games.is_empty()
+                    && game.client.is_none()
+                    && after_launch != AfterLaunch::Stay
+                {
+                    log::info!("{what} has closed, so the launcher closes too");
+                    return RunState::Close;
+                }
+                log::info!("{what} has closed");
+                self.send_info(format!("{what} has closed"));
+            }
+            GameExit::Crashed(exit) => {
+                log::error!("{what} crashed with {exit}");
+                if self.hidden {
+                    self.hidden = false;
+                    self.send(PatchMessage::HideWindow(false));
+                }
+                // A client crashing leaves the main game's status alone, the
+                // crash window is enough
+                if game.client.is_none() {
+                    self.send_error(format!("{what} closed unexpectedly with {exit}"));
+                } else {
+                    self.send_info(format!("{what} closed unexpectedly with {exit}"));
+                }
+                self.send(PatchMessage::GameCrashed(GameCrash {
+                    client: game.client.clone(),
+                    exit,
+                    while_starting: !game.has_started(),
+                    log_path: game.log_path,
+                }));
             }
+        }
+
+        // The game can be played again
+        if game.client.is_none() {
+            self.send_status(if self.offline {
+                PatchStatus::Offline
+            } else {
+                PatchStatus::Finished
+            });
+        }
+        RunState::Continue
+    }
+
+    fn has_clients_running(&self) -> bool {
+        self.games.iter().any(|game| game.client.is_some())
+    }
+
+    fn start_game(
+        &mut self,
+        session: Option<&Session>,
+        client: Option<&ClientSettings>,
+    ) -> Result<GameProcess, Box<dyn Error>> {
+        #[cfg(unix)]
+        {
+            let runner = runner::selected(&self.runners, &self.settings.wine)
+                .ok_or("No Wine runner was found")?;
+            log::info!("Using Wine runner {}", runner.label());
+            self.prepare_wine_prefix(runner)?;
+        }
+
+        let profile = match client {
+            Some(client) => client.ini_profile.trim(),
+            None => self.settings.game.ini_profile.trim(),
+        };
+        if profile.is_empty() {
+            self.backup_game_ini();
+        } else {
+            self.use_ini_profile(profile)?;
+        }
+
+        let command = LaunchCommand::new(
+            &self.settings,
+            &self.runners,
+            &self.self_dir,
+            game::game_args(&self.settings, session),
+        )?;
+        let log_name = match client {
+            Some(client) => format!("game-{}.log", file_name_safe(&client.name)),
+            None => GAME_LOG.to_string(),
+        };
+        let log_path = self.self_dir.join(LOG_DIR).join(log_name);
+        // The arguments can hold the player's session token, so they aren't
+        // logged
+        log::info!("Starting {GAME_EXE} in {:?}", command.dir);
+        let game = GameProcess::start(command, log_path, client.map(|c| c.name.clone()))?;
+
+        self.last_launch = Some(Instant::now());
+        Ok(game)
+    }
+
+    /// How long a game has to wait before its eco.ini profile can be put in
+    /// place, since a game which was just started may still be reading
+    /// eco.ini. `client` is `None` for the main game.
+    fn profile_wait(&self, client: Option<&ClientSettings>) -> Option<Duration> {
+        let profile = match client {
+            Some(client) => client.ini_profile.trim(),
+            None => self.settings.game.ini_profile.trim(),
+        };
+        if profile.is_empty() {
+            return None;
+        }
+        let waited = self.last_launch?.elapsed();
+        (waited < CLIENT_START_DELAY).then(|| CLIENT_START_DELAY - waited)
+    }
+
+    /// Starts the game which was waiting, once it is time
+    fn start_pending_launch(&mut self) {
+        if !matches!(&self.pending_launch, Some(pending) if Instant::now() >= pending.at) {
+            return;
+        }
+        if let Some(pending) = self.pending_launch.take() {
+            self.launch_game(pending.session, pending.client);
+        }
+    }
+
+    fn cancel_pending_launch(&mut self) {
+        let pending = match self.pending_launch.take() {
+            Some(pending) => pending,
+            None => return,
         };
+        log::info!("Starting the game was cancelled");
+        self.send_info("Starting the game was cancelled".to_string());
+        // The main game can be played again
+        if pending.client.is_none() {
+            self.send_status(if self.offline {
+                PatchStatus::Offline
+            } else {
+                PatchStatus::Finished
+            });
+        }
+    }
+
+    /// Puts a saved eco.ini in place for the next game client
+    fn use_ini_profile(&self, profile: &str) -> Result<(), Box<dyn Error>> {
+        log::info!("Using the {GAME_INI} profile {profile:?}");
+        game_ini::use_profile(&self.self_dir, profile)
+    }
+
+    /// Sets up the game's Wine prefix if it isn't already
+    #[cfg(unix)]
+    fn prepare_wine_prefix(&self, runner: &Runner) -> Result<(), Box<dyn Error>> {
+        if wine::is_prefix_ready(&self.self_dir) {
+            return Ok(());
+        }
+
+        self.send_download("Setting up Wine...".to_string(), 1.);
+        if let Some(warning) = wine::prepare_prefix(&self.self_dir, runner)? {
+            self.send_warning(Some(warning));
+        }
+        Ok(())
+    }
+
+    /// Finds the runners which can run the game and tells the GUI about them
+    fn find_runners(&mut self) {
+        self.runners = runner::discover(&self.settings.wine);
+        self.send(PatchMessage::Runners(self.runners.clone()));
+    }
 
-        std::env::set_current_dir(&self.self_dir)?;
-        Ok(start_detached_process(&args)?)
+    /// Replaces the settings with ones from the settings window
+    fn save_settings(&mut self, settings: Settings) {
+        // Settings which would stop downloads from working are not used, and
+        // the settings window goes back to the current ones
+        if let Err(why) = settings.network.validate() {
+            self.send_error_details(
+                ErrorCode::Settings,
+                "The network settings are invalid".to_string(),
+                why.as_ref(),
+            );
+            log::error!("Refused to save invalid network settings: {why}");
+            self.send(PatchMessage::Settings(Box::new(self.settings.clone())));
+            return;
+        }
+
+        let custom_runner_changed = settings.wine.custom_runner != self.settings.wine.custom_runner;
+        self.settings = settings;
+
+        if let Err(why) = self.settings.save(&self.self_dir) {
+            self.send_error_details(
+                ErrorCode::Settings,
+                "Failed to save settings".to_string(),
+                why.as_ref(),
+            );
+            log::error!("Failed to save settings: {why}");
+        }
+
+        match transport::for_url(&self.server_url, &self.settings.network) {
+            Ok(transport) => self.transport = transport,
+            Err(why) => log::warn!("Keeping previous network settings: {why}"),
+        }
+        // A new patch server is used from the next start
+        if self.settings_problem.is_some() && server_transport(&self.settings).is_ok() {
+            self.settings_problem = None;
+            self.send_warning(None);
+        }
+
+        if custom_runner_changed {
+            self.find_runners();
+        }
+    }
+
+    /// Sends the remembered logins to the GUI. Reading them can wait on the
+    /// OS keyring, which would freeze the GUI.
+    fn send_saved_logins(&self) {
+        if !self.settings.login.enabled {
+            return;
+        }
+
+        if let Some(saved) = credentials::load() {
+            self.send(PatchMessage::SavedLogin(saved));
+        }
+
+        let clients = self
+            .settings
+            .game
+            .clients
+            .iter()
+            .filter_map(|client| {
+                let saved = credentials::load_client(&client.name)?;
+                Some((client.name.clone(), saved))
+            })
+            .collect();
+        self.send(PatchMessage::SavedClientLogins(clients));
+    }
+
+    /// Sends the game's eco.ini to the GUI, so its options can be edited
+    fn send_game_ini(&self) {
+        let ini = game_ini::read(&self.self_dir)
+            .map(Box::new)
+            .map_err(|why| why.to_string());
+        self.send(PatchMessage::GameIni(ini));
+        self.send_ini_files();
+    }
+
+    /// Sends the names of the eco.ini profiles and backups to the GUI
+    fn send_ini_files(&self) {
+        match game_ini::profiles(&self.self_dir) {
+            Ok(profiles) => self.send(PatchMessage::IniProfiles(profiles)),
+            Err(why) => log::warn!("Failed to list {GAME_INI} profiles: {why}"),
+        }
+        match game_ini::backups(&self.self_dir) {
+            Ok(backups) => self.send(PatchMessage::IniBackups(backups)),
+            Err(why) => log::warn!("Failed to list {GAME_INI} backups: {why}"),
+        }
+    }
+
+    /// Backs up eco.ini if it has changed. Failing to do so shouldn't stop
+    /// anything else.
+    fn backup_game_ini(&self) {
+        if let Err(why) = game_ini::backup(&self.self_dir) {
+            log::warn!("Failed to back up {GAME_INI}: {why}");
+        }
+    }
+
+    fn restore_game_ini(&self, name: &str) {
+        match game_ini::restore(&self.self_dir, name) {
+            Ok(_) => self.send_info(format!("{GAME_INI} was restored")),
+            Err(why) => {
+                self.send_error_details(
+                    ErrorCode::GameIni,
+                    format!("Failed to restore {GAME_INI}"),
+                    why.as_ref(),
+                );
+                log::error!("Failed to restore {GAME_INI} from {name}: {why}");
+            }
+        }
+        self.send_game_ini();
+    }
+
+    fn save_ini_profile(&self, profile: &str) {
+        match game_ini::save_profile(&self.self_dir, profile) {
+            Ok(_) => self.send_info(format!("Saved {GAME_INI} as the profile {profile:?}")),
+            Err(why) => {
+                self.send_error_details(
+                    ErrorCode::GameIni,
+                    format!("Failed to save the {GAME_INI} profile"),
+                    why.as_ref(),
+                );
+                log::error!("Failed to save {GAME_INI} profile {profile:?}: {why}");
+            }
+        }
+        self.send_ini_files();
+    }
+
+    fn delete_ini_profile(&self, profile: &str) {
+        if let Err(why) = game_ini::delete_profile(&self.self_dir, profile) {
+            self.send_error_details(
+                ErrorCode::GameIni,
+                format!("Failed to delete the {GAME_INI} profile"),
+                why.as_ref(),
+            );
+            log::error!("Failed to delete {GAME_INI} profile {profile:?}: {why}");
+        }
+        self.send_ini_files();
+    }
+
+    /// Changes options in eco.ini from the settings window
+    fn save_game_ini(&self, changes: &[IniChange]) {
+        match game_ini::write(&self.self_dir, changes) {
+            Ok(_) => self.send_info("Game options were saved".to_string()),
+            Err(why) => {
+                self.send_error_details(
+                    ErrorCode::GameIni,
+                    "Failed to save game options".to_string(),
+                    why.as_ref(),
+                );
+                log::error!("Failed to save eco.ini: {why}");
+            }
+        }
+        self.send_game_ini();
+    }
+
+    /// Saves diagnostics for support. This checks every game file, so it can
+    /// take a while.
+    fn export_diagnostics(&self) {
+        self.send_download("Exporting diagnostics...".to_string(), 1.);
+        match bundle::export(self) {
+            Ok(path) => {
+                self.send_info(format!("Diagnostics were saved to {}", path.display()));
+                self.send(PatchMessage::DiagnosticsExported(path));
+            }
+            Err(why) => {
+                self.send_error_details(
+                    ErrorCode::Diagnostics,
+                    "Failed to export diagnostics".to_string(),
+                    why.as_ref(),
+                );
+                log::error!("Failed to export diagnostics: {why}");
+            }
+        }
+    }
+
+    /// Deletes the game's Wine prefix. It is set up again when the game is
+    /// next started.
+    fn reset_wine_prefix(&self) {
+        if !self.games.is_empty() {
+            log::warn!("Not resetting the Wine prefix while the game is running");
+            self.send_info("Close the game before resetting the Wine prefix".to_string());
+            return;
+        }
+
+        #[cfg(unix)]
+        match wine::reset_prefix(&self.self_dir) {
+            Ok(_) => self
+                .send_info("The Wine prefix will be set up again when the game starts".to_string()),
+            Err(why) => {
+                self.send_error_details(
+                    ErrorCode::WinePrefix,
+                    "Failed to reset the Wine prefix".to_string(),
+                    why.as_ref(),
+                );
+                log::error!("Failed to reset Wine prefix: {why}");
+            }
+        }
     }
 
     /// Checks for whether the current patcher is a temporary updated patcher.
@@ -374,8 +1534,12 @@ impl PatchWorker {
             Some(ext) => ext,
             None => {
                 // Remove aecoupdate launcher if there is one
-                self.remove_aecoupdate_file()
-                    .map_err(|why| why.to_patch_error("Failed to remove temporary launcher"))?;
+                self.remove_aecoupdate_file().map_err(|why| {
+                    why.to_patch_error(
+                        "Failed to remove temporary launcher",
+                        ErrorCode::LauncherUpdate,
+                    )
+                })?;
                 return Ok(RunState::Continue);
             }
         };
@@ -402,7 +1566,8 @@ impl PatchWorker {
         for retry in 1..=retries {
             if let Err(why) = std::fs::copy(&self.self_exe, &new_file_path) {
                 if retry == retries {
-                    return Err(why.to_patch_error("Failed to overwrite patcher"));
+                    return Err(why
+                        .to_patch_error("Failed to overwrite patcher", ErrorCode::LauncherUpdate));
                 }
                 std::thread::sleep(std::time::Duration::from_millis(250));
             } else {
@@ -411,12 +1576,17 @@ impl PatchWorker {
         }
 
         // Make sure the file is executable on unixlike systems
-        set_executable(&new_file_path)
-            .map_err(|why| why.to_patch_error("Failed to make patcher executable"))?;
+        set_executable(&new_file_path).map_err(|why| {
+            why.to_patch_error(
+                "Failed to make patcher executable",
+                ErrorCode::LauncherUpdate,
+            )
+        })?;
 
         // Open the restored launcher and close this one
-        start_detached_process(&[new_file_path])
-            .map_err(|why| why.to_patch_error("Failed to start new launcher"))?;
+        start_detached_process(&[new_file_path], &[]).map_err(|why| {
+            why.to_patch_error("Failed to start new launcher", ErrorCode::LauncherUpdate)
+        })?;
 
         // Signal to stop the patcher
         Ok(RunState::Close)
@@ -454,36 +1624,27 @@ impl PatchWorker {
         Ok(self.self_exe.with_file_name(file_name))
     }
 
-    /// Emil Chronicle Online expects a section in its eco.ini which is like:
-    ///
-    /// [CheckECO]
-    /// OK
-    ///
-    /// If this is not present, the game will ignore its eco.ini and load only
-    /// default configurations. The game is expected to write this before it
-    /// shuts down, but especially on Linux with Wine, this doesn't seem
-    /// to happen. If we perform this operation, then we can ensure that
-    /// Linux users' configurations actually work.
-    ///
-    /// When default configurations are loaded, undesirable things happen such
-    /// as not saving game-window size, using the incorrect in-game window
-    /// themes, and having the game be totally silent due to the volume sliders
-    /// being set to 0%.
-    fn check_eco_ini(&self) -> Result<(), Box<dyn Error>> {
-        let ini_file = self.self_dir.join(GAME_INI);
-        let ini_data = std::fs::read(&ini_file)?;
-
-        // While the game normally attempts to save this file as UTF_16, using
-        // UTF_8 here works. If we use UTF_16 here, then the case where the
-        // file was actually UTF_8 does not decode properly.
-        let (cow, _encoding, had_errors) = encoding_rs::UTF_8.decode(&ini_data);
-
-        if !had_errors && cow.find("[CheckECO]").is_none() {
-            let new_string = format!("{cow}\r\n[CheckECO]\r\nOK\r\n");
-            std::fs::write(ini_file, new_string)?;
+    /// Makes sure the game reads its eco.ini, see `game_ini::repair`. A file
+    /// which can't be read is reported, but doesn't stop the game from being
+    /// played.
+    fn check_eco_ini(&self) {
+        match game_ini::repair(&self.self_dir) {
+            Ok(Repair::Unchanged) => {}
+            Ok(Repair::Missing) => log::info!("{GAME_INI} has not been created by the game yet"),
+            Ok(Repair::Repaired(encoding)) => {
+                log::info!("Added [CheckECO] to {GAME_INI} ({})", encoding.name());
+                self.send_download(
+                    format!("Ready! {GAME_INI} was repaired so the game keeps its settings"),
+                    1.,
+                );
+            }
+            Err(why) => {
+                log::warn!("Could not check {GAME_INI}: {why}");
+                self.send_warning(Some(format!(
+                    "{GAME_INI} could not be checked, so the game may not keep its settings: {why}"
+                )));
+            }
         }
-
-        Ok(())
     }
 }
 
@@ -493,21 +1654,66 @@ impl Drop for PatchWorker {
     }
 }
 
-/// Gets a Directory child from a Directory by name, if it is present
-fn subdir_by_name<'a>(dir: &'a Directory, name: &str) -> Option<&'a Directory> {
-    for child in &dir.children {
-        if let FSObject::Directory(d) = child {
-            if d.name == name {
-                return Some(d);
-            }
-        }
+/// Checks whether this launcher is older than the server allows
+fn is_launcher_outdated(status: &StatusDocument) -> bool {
+    match (&status.min_launcher_version, pkg_version()) {
+        (Some(minimum), Some(current)) => compare_versions(current, minimum).is_lt(),
+        _ => false,
     }
-    None
 }
 
-/// Starts a new process and closes the current one.
-fn start_detached_process(args: &[impl AsRef<OsStr>]) -> Result<(), PopenError> {
-    match subprocess::Popen::create(args, subprocess::PopenConfig::default()) {
+/// Gets the URL of the patch server, which can be changed in the settings.
+/// Besides http, https and file URLs, this accepts a plain directory path
+/// such as `D:\ecopatch\saga10\`.
+/// Removes the remembered login. This can wait on the OS keyring, so it isn't
+/// done on the GUI thread.
+fn forget_login() {
+    if let Err(why) = credentials::forget() {
+        log::warn!("Could not forget remembered login: {why}");
+    }
+}
+
+/// The patch server and the transport to reach it with, from the settings
+fn server_transport(
+    settings: &Settings,
+) -> Result<(reqwest::Url, Box<dyn Transport>), Box<dyn Error>> {
+    settings.network.validate()?;
+    let server_url = patch_server_url(settings)?;
+    let transport = transport::for_url(&server_url, &settings.network)?;
+    Ok((server_url, transport))
+}
+
+fn patch_server_url(settings: &Settings) -> Result<reqwest::Url, Box<dyn Error>> {
+    let server = settings.patch_server.as_deref().unwrap_or(PATCH_SERVER);
+
+    let mut url = match reqwest::Url::parse(server) {
+        // Windows drive letters would otherwise be mistaken for URL schemes
+        Ok(url) if url.scheme().len() > 1 => url,
+        _ => reqwest::Url::from_directory_path(server)
+            .map_err(|_| format!("Invalid patch server location: {server}"))?,
+    };
+
+    // Other URLs are joined onto this one, which only works as expected if it
+    // ends with a slash
+    if !url.path().ends_with('/') {
+        url.set_path(&format!("{}/", url.path()));
+    }
+
+    Ok(url)
+}
+
+/// Starts a process which keeps running after the launcher closes. `env` is
+/// added to the launcher's environment.
+fn start_detached_process(
+    args: &[impl AsRef<OsStr>],
+    env: &[(OsString, OsString)],
+) -> Result<(), PopenError> {
+    let config = subprocess::PopenConfig {
+        env: process_env(env),
+        ..subprocess::PopenConfig::default()
+    };
+
+    match subprocess::Popen::create(args, config) {
         Ok(mut popen) => {
             // Close this program
             popen.detach();
@@ -0,0 +1,199 @@
+use crate::settings::WineSettings;
+use std::path::{Path, PathBuf};
+use subprocess::{Exec, Redirection};
+
+/// Places where Wine is commonly installed besides PATH
+const WINE_PATHS: &[&str] = &[
+    "/opt/wine-staging/bin/wine",
+    "/opt/wine-devel/bin/wine",
+    "/opt/wine-stable/bin/wine",
+    "/usr/local/bin/wine",
+];
+
+/// Directories inside the home directory which contain Proton installs
+const PROTON_DIRS: &[&str] = &[
+    ".steam/steam/steamapps/common",
+    ".steam/root/compatibilitytools.d",
+    ".local/share/Steam/steamapps/common",
+    ".local/share/Steam/compatibilitytools.d",
+    ".var/app/com.valvesoftware.Steam/data/Steam/steamapps/common",
+    ".var/app/com.valvesoftware.Steam/data/Steam/compatibilitytools.d",
+];
+
+/// Directories inside the home directory which contain Wine builds
+const WINE_BUILD_DIRS: &[&str] = &[".local/share/lutris/runners/wine"];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RunnerKind {
+    Wine,
+    WineStaging,
+    Proton,
+    /// Given by the player in the settings
+    Custom,
+}
+
+/// A Wine build which can run the game
+#[derive(Clone)]
+pub struct Runner {
+    pub kind: RunnerKind,
+    /// Where the runner was found, shown to the player
+    pub name: String,
+    /// The wine binary. This also identifies the runner in the settings.
+    pub wine: PathBuf,
+    /// Output of `wine --version`, if it could be run
+    pub version: Option<String>,
+}
+
+impl Runner {
+    /// Identifies the runner in the settings
+    pub fn id(&self) -> String {
+        self.wine.to_string_lossy().to_string()
+    }
+
+    /// The wineserver belonging to this runner
+    pub fn wineserver(&self) -> PathBuf {
+        let wineserver = self.wine.with_file_name("wineserver");
+        if wineserver.exists() {
+            wineserver
+        } else {
+            PathBuf::from("wineserver")
+        }
+    }
+
+    /// Describes the runner for the runner picker
+    pub fn label(&self) -> String {
+        let kind = match self.kind {
+            RunnerKind::Wine => "Wine",
+            RunnerKind::WineStaging => "Wine Staging",
+            RunnerKind::Proton => "Proton",
+            RunnerKind::Custom => "Custom",
+        };
+        match &self.version {
+            Some(version) => format!("{kind}: {} ({version})", self.name),
+            None => format!("{kind}: {}", self.name),
+        }
+    }
+}
+
+/// Finds every runner on this computer, including the custom one from the
+/// settings. The custom runner comes first, then system Wine.
+pub fn discover(settings: &WineSettings) -> Vec<Runner> {
+    let mut candidates = Vec::new();
+
+    if !settings.custom_runner.is_empty() {
+        let wine = PathBuf::from(&settings.custom_runner);
+        candidates.push((RunnerKind::Custom, settings.custom_runner.clone(), wine));
+    }
+
+    if let Some(wine) = find_in_path("wine") {
+        candidates.push((RunnerKind::Wine, "System".to_string(), wine));
+    }
+    for path in WINE_PATHS {
+        candidates.push((RunnerKind::Wine, path.to_string(), PathBuf::from(path)));
+    }
+
+    if let Some(home_dir) = dirs::home_dir() {
+        for dir in PROTON_DIRS {
+            for (name, install_dir) in subdirs(&home_dir.join(dir)) {
+                // Older Proton versions keep their files in "dist"
+                for files in ["files", "dist"] {
+                    let wine = install_dir.join(files).join("bin").join("wine");
+                    candidates.push((RunnerKind::Proton, name.clone(), wine));
+                }
+            }
+        }
+        for dir in WINE_BUILD_DIRS {
+            for (name, install_dir) in subdirs(&home_dir.join(dir)) {
+                let wine = install_dir.join("bin").join("wine");
+                candidates.push((RunnerKind::Wine, name, wine));
+            }
+        }
+    }
+
+    let mut runners: Vec<Runner> = Vec::new();
+    for (kind, name, wine) in candidates {
+        if !wine.is_file() {
+            continue;
+        }
+
+        // The same Wine can be found more than once through symlinks
+        let real_path = wine.canonicalize().unwrap_or_else(|_| wine.clone());
+        let is_duplicate = runners
+            .iter()
+            .any(|runner| runner.wine.canonicalize().ok().as_ref() == Some(&real_path));
+        if is_duplicate {
+            continue;
+        }
+
+        let version = wine_version(&wine);
+        let kind = match &version {
+            Some(version) if kind == RunnerKind::Wine && version.contains("Staging") => {
+                RunnerKind::WineStaging
+            }
+            _ => kind,
+        };
+
+        runners.push(Runner {
+            kind,
+            name,
+            wine,
+            version,
+        });
+    }
+
+    runners
+}
+
+/// Picks the runner to use: the one chosen in the settings if it still
+/// exists, otherwise the first one found
+pub fn selected<'a>(runners: &'a [Runner], settings: &WineSettings) -> Option<&'a Runner> {
+    let chosen = settings
+        .runner
+        .as_ref()
+        .and_then(|id| runners.iter().find(|runner| &runner.id() == id));
+    chosen.or(runners.first())
+}
+
+/// Runs `wine --version`
+fn wine_version(wine: &Path) -> Option<String> {
+    let capture = Exec::cmd(wine)
+        .arg("--version")
+        .stdout(Redirection::Pipe)
+        .stderr(Redirection::Pipe)
+        .capture()
+        .ok()?;
+
+    let version = capture.stdout_str().trim().to_string();
+    if capture.success() && !version.is_empty() {
+        Some(version)
+    } else {
+        None
+    }
+}
+
+/// Searches PATH for an executable
+fn find_in_path(name: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(name))
+        .find(|file| file.is_file())
+}
+
+/// Lists the subdirectories of `dir` with their names, sorted by name
+fn subdirs(dir: &Path) -> Vec<(String, PathBuf)> {
+    let mut subdirs: Vec<(String, PathBuf)> = match std::fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(Result::ok)
+            .filter(|entry| entry.path().is_dir())
+            .map(|entry| {
+                (
+                    entry.file_name().to_string_lossy().to_string(),
+                    entry.path(),
+                )
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    subdirs.sort();
+    subdirs
+}
@@ -0,0 +1,225 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+/// Name of the settings file, which is kept next to the launcher
+pub const SETTINGS_FILE: &str = "launcher-settings.json";
+
+/// User-editable launcher settings. Missing fields are filled in with their
+/// defaults, so older settings files keep working as new settings are added.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Overrides where patches are downloaded from. Besides the default web
+    /// server, this can be a `file://` URL or a directory path, such as a
+    /// copy of the patch server on a USB drive or LAN share.
+    pub patch_server: Option<String>,
+    pub network: NetworkSettings,
+    pub status_polling: StatusPollingSettings,
+    pub login: LoginSettings,
+    pub wine: WineSettings,
+    pub game: GameSettings,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkSettings {
+    /// How the launcher decides which proxy to use
+    pub proxy_mode: ProxyMode,
+    /// Proxy URL used in `ProxyMode::Manual`, such as `http://proxy:8080` or
+    /// `socks5://proxy:1080`
+    pub proxy_url: String,
+    /// Optional username for the manual proxy
+    pub proxy_username: String,
+    /// Optional password for the manual proxy
+    pub proxy_password: String,
+    /// Comma separated hosts which should bypass the manual proxy, such as
+    /// `localhost, 192.168.0.0/16`. When empty, `NO_PROXY` is used instead.
+    pub no_proxy: String,
+    /// Seconds to wait while connecting to the patch server
+    pub connect_timeout: u64,
+    /// Seconds to wait for more data from the patch server before giving up
+    pub read_timeout: u64,
+}
+
+impl NetworkSettings {
+    /// Checks for values which would stop every download from working
+    pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+        if self.connect_timeout == 0 {
+            return Err("The connect timeout must be at least 1 second".into());
+        }
+        if self.read_timeout == 0 {
+            return Err("The read timeout must be at least 1 second".into());
+        }
+        Ok(())
+    }
+}
+
+impl Default for NetworkSettings {
+    fn default() -> Self {
+        Self {
+            proxy_mode: ProxyMode::System,
+            proxy_url: String::new(),
+            proxy_username: String::new(),
+            proxy_password: String::new(),
+            no_proxy: String::new(),
+            connect_timeout: 30,
+            read_timeout: 60,
+        }
+    }
+}
+
+/// Controls how the launcher waits for maintenance to end
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StatusPollingSettings {
+    /// Seconds to wait before checking the server status again. This doubles
+    /// after every check, up to `max_interval`. 0 turns off polling, so the
+    /// player has to press RETRY instead.
+    pub interval: u64,
+    /// The longest time in seconds to wait between checks
+    pub max_interval: u64,
+    /// Draw attention to the launcher once the server is back online
+    pub notify_when_online: bool,
+}
+
+impl Default for StatusPollingSettings {
+    fn default() -> Self {
+        Self {
+            interval: 30,
+            max_interval: 300,
+            notify_when_online: true,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoginSettings {
+    /// Ask for a username and password, and log in before starting the game
+    pub enabled: bool,
+    /// The control panel's login endpoint, which hands out session tokens.
+    /// It has to use HTTPS, unless it is a local mock server for testing.
+    pub endpoint: String,
+    /// Arguments given to the game after logging in. `{username}` and
+    /// `{token}` are replaced with the player's login details. The password
+    /// is never passed on, since other programs can see the arguments.
+    pub launch_args: Vec<String>,
+}
+
+impl Default for LoginSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: String::new(),
+            launch_args: vec!["/launch".to_string()],
+        }
+    }
+}
+
+/// How the game is run on Unixlike systems
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WineSettings {
+    /// Path of the wine binary to use. When missing, or when the runner can't
+    /// be found anymore, the first runner found is used.
+    pub runner: Option<String>,
+    /// Path of a wine binary which isn't found automatically
+    pub custom_runner: String,
+    /// Environment variables for the game, such as `WINEDEBUG` or `DXVK_HUD`,
+    /// by the path of the wine binary they are used with
+    pub runner_env: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+/// How the game is started and watched
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameSettings {
+    /// What the launcher does once the game has started
+    pub after_launch: AfterLaunch,
+    /// Arguments given to the game after the usual ones, such as debug flags
+    pub extra_args: Vec<String>,
+    /// Environment variables for the game. On Unixlike systems, the ones for
+    /// the runner take precedence.
+    pub env: BTreeMap<String, String>,
+    /// Directory the game is started in. Relative paths are inside the game
+    /// directory, and an empty path is the game directory itself.
+    pub working_dir: String,
+    /// Name of a saved eco.ini to put in place before the game starts, or
+    /// empty to use the current eco.ini
+    pub ini_profile: String,
+    /// Extra copies of the game which can be started next to the main one
+    pub clients: Vec<ClientSettings>,
+}
+
+/// An extra copy of the game, such as for a support character
+#[derive(Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClientSettings {
+    /// Shown in the launcher, and identifies the client's remembered login
+    pub name: String,
+    /// Account the client logs in with
+    pub username: String,
+    /// Name of a saved eco.ini to use for this client, or empty to use the
+    /// current eco.ini
+    pub ini_profile: String,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AfterLaunch {
+    /// Close once the game has started without crashing
+    #[default]
+    Close,
+    /// Hide until the game exits, then close. The launcher comes back if the
+    /// game crashes.
+    Hide,
+    /// Stay open until the player closes the launcher
+    Stay,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyMode {
+    /// Use the proxy configured by the OS or by the `HTTP_PROXY`,
+    /// `HTTPS_PROXY`, `ALL_PROXY` and `NO_PROXY` environment variables
+    System,
+    /// Always connect directly
+    None,
+    /// Use the proxy given in the settings file
+    Manual,
+}
+
+impl Settings {
+    /// Gets the path of the settings file for a launcher in `dir`
+    pub fn path(dir: &Path) -> PathBuf {
+        dir.join(SETTINGS_FILE)
+    }
+
+    /// Loads the settings file from `dir`. If there is no settings file yet,
+    /// one is created with the default settings so that users can find and
+    /// edit it.
+    pub fn load(dir: &Path) -> Result<Self, Box<dyn Error>> {
+        let path = Self::path(dir);
+        if !path.exists() {
+            let settings = Self::default();
+            if let Err(why) = settings.save(dir) {
+                log::warn!("Could not create settings file: {why}");
+            }
+            return Ok(settings);
+        }
+
+        let json_bytes = std::fs::read(&path)?;
+        Ok(serde_json::from_slice(&json_bytes)?)
+    }
+
+    /// Writes the settings file to `dir`. Network settings which can't work
+    /// are refused.
+    pub fn save(&self, dir: &Path) -> Result<(), Box<dyn Error>> {
+        self.network.validate()?;
+        let json_bytes = serde_json::to_vec_pretty(self)?;
+        std::fs::write(Self::path(dir), json_bytes)?;
+        Ok(())
+    }
+}
@@ -1,7 +1,22 @@
-use crate::message::{GUIMessage, PatchMessage, PatchStatus};
+use crate::branding::{default_links, LinkButton};
+use crate::credentials::Credentials;
+use crate::ini::{self, IniChange, IniFile, OptionKind, KNOWN_OPTIONS};
+use crate::logging::{self, LogLine};
+use crate::message::{
+    Check, CheckStatus, ErrorDetails, GUIMessage, GameCrash, IniBackup, PatchMessage, PatchStatus,
+    ServerNotice,
+};
+use crate::news::{parse_body, Block, Inline, News};
+use crate::patcher::LaunchCommand;
+use crate::runner::{self, Runner};
+use crate::settings::{AfterLaunch, ClientSettings, Settings};
 use crate::version::version_summary;
+use chrono::TimeZone;
 use eframe::{egui, emath::Vec2};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
 use std::sync::mpsc::{Receiver, Sender};
+use std::time::{Duration, SystemTime};
 mod atomix;
 
 fn load_image_from_memory(image_data: &[u8]) -> Result<egui::ColorImage, image::ImageError> {
@@ -15,6 +30,29 @@ fn load_image_from_memory(image_data: &[u8]) -> Result<egui::ColorImage, image::
     ))
 }
 
+/// Formats the time left until an event, such as `1d 02:03:04`
+fn countdown_string(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let days = seconds / 86400;
+    let hours = seconds / 3600 % 24;
+    let minutes = seconds / 60 % 60;
+    let seconds = seconds % 60;
+
+    if days > 0 {
+        format!("{days}d {hours:02}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{hours:02}:{minutes:02}:{seconds:02}")
+    }
+}
+
+/// Formats a news date, such as `March 4, 2023`
+fn date_string(timestamp: i64) -> Option<String> {
+    chrono::Local
+        .timestamp_opt(timestamp, 0)
+        .single()
+        .map(|date| date.format("%B %-d, %Y").to_string())
+}
+
 enum ProgressBarState {
     Downloading(String, f32),
     Connecting(String),
@@ -23,8 +61,12 @@ enum ProgressBarState {
 
 enum PlayButtonState {
     Disabled,
+    Working,
+    Paused,
+    Offline,
     Play,
     Retry,
+    Playing,
 }
 
 impl ProgressBarState {
@@ -65,17 +107,97 @@ impl ProgressBarState {
     }
 }
 
+const WINDOW_TITLE: &str = "Atomix ECO Launcher";
+
 pub struct PatcherUI {
     tx: Sender<GUIMessage>,
     rx: Receiver<PatchMessage>,
     background_handle: Option<egui::TextureHandle>,
+    /// Background image from the patch server, shown instead of the built-in
+    /// one if it can be loaded
+    server_background: Option<Vec<u8>>,
+    links: Vec<LinkButton>,
     link_bar_color: egui::Color32,
     username: String,
     password: String,
+    /// Waiting for the login server to answer
+    logging_in: bool,
+    /// Remember the login after logging in
+    remember_login: bool,
+    /// Whether a login is remembered, which can be forgotten
+    has_saved_login: bool,
+    login_error: Option<String>,
     progress_bar_state: ProgressBarState,
+    /// Details of the error in the progress bar, if it has any
+    error_details: Option<ErrorDetails>,
+    error_details_open: bool,
     play_button_state: PlayButtonState,
+    warning: Option<String>,
+    server_notice: Option<ServerNotice>,
+    news: Option<News>,
+    /// The parsed body of each news item
+    news_blocks: Vec<Vec<Block>>,
+    /// News images by location, loaded the first time they are shown. Images
+    /// which could not be loaded are `None`.
+    news_textures: HashMap<String, Option<egui::TextureHandle>>,
+    /// Text shown in the title to draw attention to the launcher, until the
+    /// player clicks somewhere in the window
+    notification: Option<String>,
     program_version: String,
     use_login: bool,
+    settings_open: bool,
+    /// Results of the setup checks, shown until the player closes them
+    diagnostics: Option<Vec<Check>>,
+    /// Settings as last saved, from the worker
+    settings: Option<Settings>,
+    /// Settings being edited in the settings window
+    settings_draft: Option<Settings>,
+    runners: Vec<Runner>,
+    /// Where the game is installed, for the launch options preview
+    game_dir: PathBuf,
+    /// New environment variable for the runner being entered in the settings
+    /// window
+    new_env_key: String,
+    new_env_value: String,
+    /// New launch options being entered in the settings window
+    new_game_arg: String,
+    new_game_env_key: String,
+    new_game_env_value: String,
+    /// The reset button was pressed once, and needs to be pressed again
+    confirm_reset_prefix: bool,
+    /// The last game crash, shown until the player closes it
+    game_crash: Option<GameCrash>,
+    clients_open: bool,
+    log_open: bool,
+    /// The least severe level shown in the log viewer
+    log_level: log::Level,
+    /// Names of the extra game clients which are running
+    running_clients: Vec<String>,
+    /// Logins entered for the extra game clients, by client name. Remembered
+    /// logins are filled in the first time a client is shown.
+    client_logins: HashMap<String, ClientLogin>,
+    /// Remembered logins of the extra game clients, from the worker
+    saved_client_logins: HashMap<String, Credentials>,
+    /// The game's eco.ini as last read by the worker, or why it can't be
+    /// edited
+    game_ini: Option<Result<IniFile, String>>,
+    /// eco.ini being edited in the settings window
+    game_ini_draft: Option<IniFile>,
+    /// Names of the saved eco.ini profiles
+    ini_profiles: Vec<String>,
+    /// Name for a new eco.ini profile being entered in the settings window
+    new_ini_profile: String,
+    /// Backups of eco.ini, newest first
+    ini_backups: Vec<IniBackup>,
+    /// File name of the backup picked for restoring
+    selected_backup: Option<String>,
+}
+
+/// The login of an extra game client
+#[derive(Default)]
+struct ClientLogin {
+    password: String,
+    remember: bool,
 }
 
 impl PatcherUI {
@@ -84,19 +206,64 @@ impl PatcherUI {
         receiver: Receiver<PatchMessage>,
         use_login: bool,
     ) -> PatcherUI {
+        // The game is installed next to the launcher
+        let game_dir = std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(PathBuf::from))
+            .unwrap_or_default();
+
         PatcherUI {
             tx: sender,
             rx: receiver,
             background_handle: None,
+            server_background: None,
+            links: default_links(),
             link_bar_color: egui::Color32::from_rgba_unmultiplied(0, 0, 0, 240),
             username: String::new(),
             password: String::new(),
+            logging_in: false,
+            remember_login: false,
+            has_saved_login: false,
+            login_error: None,
             progress_bar_state: ProgressBarState::Connecting(
                 "Waiting for patch server...".to_string(),
             ),
+            error_details: None,
+            error_details_open: false,
             play_button_state: PlayButtonState::Disabled,
+            warning: None,
+            server_notice: None,
+            news: None,
+            news_blocks: Vec::new(),
+            news_textures: HashMap::new(),
+            notification: None,
             program_version: version_summary(),
             use_login,
+            settings_open: false,
+            diagnostics: None,
+            settings: None,
+            settings_draft: None,
+            runners: Vec::new(),
+            game_dir,
+            new_env_key: String::new(),
+            new_env_value: String::new(),
+            new_game_arg: String::new(),
+            new_game_env_key: String::new(),
+            new_game_env_value: String::new(),
+            confirm_reset_prefix: false,
+            game_crash: None,
+            clients_open: false,
+            log_open: false,
+            log_level: log::Level::Info,
+            running_clients: Vec::new(),
+            client_logins: HashMap::new(),
+            saved_client_logins: HashMap::new(),
+            game_ini: None,
+            game_ini_draft: None,
+            ini_profiles: Vec::new(),
+            new_ini_profile: String::new(),
+            ini_backups: Vec::new(),
+            selected_backup: None,
         }
     }
 
@@ -106,7 +273,7 @@ impl PatcherUI {
             y: 600.0,
         });
         eframe::run_native(
-            "Atomix ECO Launcher",
+            WINDOW_TITLE,
             eframe::NativeOptions {
                 initial_window_size: window_size,
                 min_window_size: window_size,
@@ -126,8 +293,16 @@ impl PatcherUI {
         if let Some(handle) = &self.background_handle {
             return handle.clone();
         }
-        let bg_img = load_image_from_memory(include_bytes!("../../assets/top_bg.png"))
-            .expect("Background texture should be valid");
+        let server_img = self.server_background.as_deref().and_then(|image_data| {
+            load_image_from_memory(image_data)
+                .map_err(|why| log::warn!("Could not load background from patch server: {why}"))
+                .ok()
+        });
+        let bg_img = match server_img {
+            Some(server_img) => server_img,
+            None => load_image_from_memory(include_bytes!("../../assets/top_bg.png"))
+                .expect("Background texture should be valid"),
+        };
         let handle = ui
             .ctx()
             .load_texture("background", bg_img, egui::TextureFilter::Linear);
@@ -143,6 +318,12 @@ impl PatcherUI {
             match message {
                 PatchMessage::Error(message) => {
                     self.progress_bar_state = ProgressBarState::Error(message);
+                    // Any details follow the message
+                    self.error_details = None;
+                    self.error_details_open = false;
+                }
+                PatchMessage::ErrorDetails(details) => {
+                    self.error_details = Some(details);
                 }
                 PatchMessage::Downloading(message, progress) => {
                     self.progress_bar_state = ProgressBarState::Downloading(message, progress);
@@ -150,7 +331,100 @@ impl PatcherUI {
                 PatchMessage::Info(message) => {
                     self.progress_bar_state = ProgressBarState::Connecting(message);
                 }
+                PatchMessage::Warning(message) => {
+                    self.warning = message;
+                }
+                PatchMessage::ServerNotice(notice) => {
+                    self.server_notice = Some(notice);
+                }
+                PatchMessage::News(news) => {
+                    self.news_blocks = news
+                        .items
+                        .iter()
+                        .map(|item| parse_body(&item.body))
+                        .collect();
+                    self.news_textures.clear();
+                    self.news = Some(news);
+                }
+                PatchMessage::Branding(branding) => {
+                    self.links = branding.links;
+                    // Reloaded on the next frame
+                    self.server_background = branding.background;
+                    self.background_handle = None;
+                }
+                PatchMessage::Notify(text) => {
+                    frame.set_window_title(&format!("{text} - {WINDOW_TITLE}"));
+                    self.notification = Some(text);
+                }
+                PatchMessage::Settings(settings) => {
+                    self.settings = Some(*settings);
+                }
+                PatchMessage::Runners(runners) => {
+                    self.runners = runners;
+                }
+                PatchMessage::Diagnostics(checks) => {
+                    // The checks can stop a login before it starts
+                    self.logging_in = false;
+                    self.diagnostics = Some(checks);
+                }
+                PatchMessage::SavedLogin(saved) => {
+                    // Don't replace what the player has started typing
+                    if self.username.is_empty() && self.password.is_empty() {
+                        self.username = saved.username;
+                        self.password = saved.password;
+                        self.remember_login = true;
+                    }
+                    self.has_saved_login = true;
+                }
+                PatchMessage::SavedClientLogins(saved) => {
+                    self.saved_client_logins = saved.into_iter().collect();
+                }
+                PatchMessage::LoginForgotten => {
+                    self.password.clear();
+                    self.remember_login = false;
+                    self.has_saved_login = false;
+                }
+                PatchMessage::LoginError(message) => {
+                    self.logging_in = false;
+                    self.login_error = Some(message);
+                }
+                PatchMessage::HideWindow(hidden) => {
+                    frame.set_visible(!hidden);
+                }
+                PatchMessage::GameCrashed(crash) => {
+                    self.game_crash = Some(crash);
+                }
+                PatchMessage::RunningClients(names) => {
+                    self.running_clients = names;
+                }
+                PatchMessage::GameIni(ini) => {
+                    self.game_ini = Some(ini.map(|ini| *ini));
+                    // Edits are made to the new eco.ini instead
+                    self.game_ini_draft = None;
+                }
+                PatchMessage::DiagnosticsExported(path) => {
+                    // Show the player where the file is, so they can send it
+                    if let Some(dir) = path.parent() {
+                        if let Err(why) = open::that(dir) {
+                            log::warn!("Could not open {dir:?}: {why}");
+                        }
+                    }
+                }
+                PatchMessage::IniProfiles(profiles) => {
+                    self.ini_profiles = profiles;
+                }
+                PatchMessage::IniBackups(backups) => {
+                    if !backups
+                        .iter()
+                        .any(|backup| Some(&backup.name) == self.selected_backup.as_ref())
+                    {
+                        self.selected_backup = None;
+                    }
+                    self.ini_backups = backups;
+                }
                 PatchMessage::PatchStatus(status) => {
+                    // A login ends with the game starting or failing to start
+                    self.logging_in = false;
                     match status {
                         PatchStatus::Finished => {
                             self.progress_bar_state =
@@ -158,11 +432,20 @@ impl PatcherUI {
                             self.play_button_state = PlayButtonState::Play;
                         }
                         PatchStatus::Working => {
-                            self.play_button_state = PlayButtonState::Disabled;
+                            self.play_button_state = PlayButtonState::Working;
+                        }
+                        PatchStatus::Paused => {
+                            self.play_button_state = PlayButtonState::Paused;
+                        }
+                        PatchStatus::Offline => {
+                            self.play_button_state = PlayButtonState::Offline;
                         }
                         PatchStatus::Error => {
                             self.play_button_state = PlayButtonState::Retry;
                         }
+                        PatchStatus::Playing => {
+                            self.play_button_state = PlayButtonState::Playing;
+                        }
                         PatchStatus::Close => {
                             // We are done!
                             frame.close();
@@ -175,7 +458,19 @@ impl PatcherUI {
 
     fn window(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
         self.handle_messages(frame);
-        atomix::window_frame(ctx, frame, "Atomix ECO Launcher", |ui| {
+
+        // The player has noticed the notification once they click somewhere
+        if self.notification.is_some() && ctx.input().pointer.any_click() {
+            frame.set_window_title(WINDOW_TITLE);
+            self.notification = None;
+        }
+
+        let title = match &self.notification {
+            Some(text) => text.clone(),
+            None => WINDOW_TITLE.to_string(),
+        };
+
+        atomix::window_frame(ctx, frame, &title, |ui| {
             self.background(ui);
             self.central_panel(ui);
         });
@@ -209,21 +504,1146 @@ impl PatcherUI {
 
     fn central_panel(&mut self, ui: &mut egui::Ui) {
         self.bottom_panel(ui);
+        self.warning_banner(ui);
+        self.notice_panel(ui);
+        self.news_panel(ui);
         if self.use_login {
             self.login_panel(ui);
         }
+        self.settings_window(ui.ctx());
+        self.diagnostics_window(ui.ctx());
+        self.clients_window(ui.ctx());
+        self.game_crash_window(ui.ctx());
+        self.log_window(ui.ctx());
+        self.error_details_window(ui.ctx());
+    }
+
+    /// Lists the extra game clients, so they can be started and watched
+    fn clients_window(&mut self, ctx: &egui::Context) {
+        let clients = match &self.settings {
+            Some(settings) if self.clients_open => settings.game.clients.clone(),
+            _ => return,
+        };
+
+        // Clients can be started whenever the game could be played
+        let can_start = matches!(
+            self.play_button_state,
+            PlayButtonState::Play | PlayButtonState::Offline | PlayButtonState::Playing
+        );
+
+        let mut open = true;
+        let mut start = Vec::new();
+
+        egui::Window::new("Clients")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-20., -160.))
+            .show(ctx, |ui| {
+                window_text_styles(ui);
+
+                if clients.is_empty() {
+                    ui.label("Add clients in Settings to play more than one character.");
+                    return;
+                }
+
+                egui::Grid::new("clients").num_columns(4).show(ui, |ui| {
+                    for client in &clients {
+                        let is_running = self.running_clients.contains(&client.name);
+                        let use_login = self.use_login;
+                        let login = self.client_login(client);
+                        ui.label(&client.name);
+
+                        if use_login {
+                            ui.add(
+                                egui::TextEdit::singleline(&mut login.password)
+                                    .password(true)
+                                    .hint_text(format!("Password for {}", client.username))
+                                    .desired_width(180.),
+                            );
+                            ui.checkbox(&mut login.remember, "Remember");
+                        } else {
+                            ui.label("");
+                            ui.label("");
+                        }
+
+                        if is_running {
+                            ui.label("Running");
+                        } else if ui
+                            .add_enabled(can_start, egui::Button::new("Start"))
+                            .clicked()
+                        {
+                            start.push(client.name.clone());
+                        }
+                        ui.end_row();
+                    }
+                });
+
+                ui.separator();
+                if ui
+                    .add_enabled(can_start, egui::Button::new("Start all"))
+                    .clicked()
+                {
+                    start = clients
+                        .iter()
+                        .map(|client| client.name.clone())
+                        .filter(|name| !self.running_clients.contains(name))
+                        .collect();
+                }
+            });
+
+        for name in start {
+            self.start_client(&name);
+        }
+        self.clients_open = open;
+    }
+
+    fn export_diagnostics_button(&mut self, ui: &mut egui::Ui) {
+        if ui
+            .add_enabled(!self.is_patching(), egui::Button::new("Export diagnostics"))
+            .on_hover_text(
+                "Saves logs, settings and a check of the game files for support. \
+                 Passwords are left out.",
+            )
+            .on_disabled_hover_text("Diagnostics can be exported once patching has finished")
+            .clicked()
+        {
+            self.send(GUIMessage::ExportDiagnostics);
+        }
+    }
+
+    /// Explains the error in the progress bar, with the details support needs
+    fn error_details_window(&mut self, ctx: &egui::Context) {
+        let details = match &self.error_details {
+            Some(details) if self.error_details_open => details.clone(),
+            _ => return,
+        };
+
+        let mut open = true;
+        egui::Window::new(format!("Error {}", details.code))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0., -40.))
+            .show(ctx, |ui| {
+                window_text_styles(ui);
+                ui.set_width(480.);
+
+                ui.add(egui::Label::new(egui::RichText::new(&details.message).strong()).wrap(true));
+                ui.add(egui::Label::new(egui::RichText::new(&details.remedy).italics()).wrap(true));
+
+                egui::CollapsingHeader::new("Technical details")
+                    .default_open(false)
+                    .show(ui, |ui| {
+                        for (depth, cause) in details.causes.iter().enumerate() {
+                            let text = match depth {
+                                0 => cause.clone(),
+                                _ => format!("caused by: {cause}"),
+                            };
+                            ui.horizontal(|ui| {
+                                ui.add_space(depth as f32 * 12.);
+                                ui.add(
+                                    egui::Label::new(egui::RichText::new(text).monospace())
+                                        .wrap(true),
+                                );
+                            });
+                        }
+                    });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui
+                        .button("Copy details")
+                        .on_hover_text("Copies the error code and details, to send to support")
+                        .clicked()
+                    {
+                        ui.output().copied_text = details.to_string();
+                    }
+                    if ui.button("Show log").clicked() {
+                        self.log_open = true;
+                    }
+                    self.export_diagnostics_button(ui);
+                });
+            });
+        self.error_details_open = open;
+    }
+
+    /// Shows what the launcher has logged recently
+    fn log_window(&mut self, ctx: &egui::Context) {
+        if !self.log_open {
+            return;
+        }
+
+        let mut open = true;
+        egui::Window::new("Log")
+            .open(&mut open)
+            .collapsible(false)
+            .default_size(egui::vec2(640., 360.))
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0., -40.))
+            .show(ctx, |ui| {
+                window_text_styles(ui);
+                let lines = logging::recent_lines(self.log_level);
+
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_label("Show")
+                        .selected_text(log_level_name(self.log_level))
+                        .show_ui(ui, |ui| {
+                            for level in [
+                                log::Level::Error,
+                                log::Level::Warn,
+                                log::Level::Info,
+                                log::Level::Debug,
+                            ] {
+                                ui.selectable_value(
+                                    &mut self.log_level,
+                                    level,
+                                    log_level_name(level),
+                                );
+                            }
+                        });
+
+                    if ui.button("Copy").clicked() {
+                        let text: Vec<String> = lines.iter().map(LogLine::to_string).collect();
+                        ui.output().copied_text = text.join("\n");
+                    }
+
+                    self.export_diagnostics_button(ui);
+
+                    match logging::log_dir() {
+                        Some(log_dir) => {
+                            if ui.button("Open log folder").clicked() {
+                                if let Err(why) = open::that(&log_dir) {
+                                    log::warn!("Could not open log folder: {why}");
+                                }
+                            }
+                        }
+                        None => {
+                            ui.label("The log could not be saved to a file");
+                        }
+                    }
+                });
+                ui.separator();
+
+                let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+                egui::ScrollArea::both()
+                    .auto_shrink([false, false])
+                    .stick_to_bottom(true)
+                    .show_rows(ui, row_height, lines.len(), |ui, rows| {
+                        for line in &lines[rows] {
+                            let color = match line.level {
+                                log::Level::Error => egui::Color32::from_rgb(0xF0, 0x80, 0x80),
+                                log::Level::Warn => egui::Color32::from_rgb(0xF0, 0xC0, 0x60),
+                                log::Level::Info => ui.visuals().text_color(),
+                                _ => egui::Color32::GRAY,
+                            };
+                            ui.add(
+                                egui::Label::new(
+                                    egui::RichText::new(line.to_string())
+                                        .monospace()
+                                        .color(color),
+                                )
+                                .wrap(false),
+                            );
+                        }
+                    });
+            });
+        self.log_open = open;
+    }
+
+    /// Gets what was entered for a client's login, starting with its
+    /// remembered login if it has one
+    fn client_login(&mut self, client: &ClientSettings) -> &mut ClientLogin {
+        let saved_client_logins = &mut self.saved_client_logins;
+        self.client_logins
+            .entry(client.name.clone())
+            .or_insert_with(|| {
+                match saved_client_logins.remove(&client.name) {
+                    // The account may have changed in the settings since
+                    Some(saved) if saved.username == client.username => ClientLogin {
+                        password: saved.password,
+                        remember: true,
+                    },
+                    _ => ClientLogin::default(),
+                }
+            })
+    }
+
+    fn start_client(&mut self, name: &str) {
+        let (password, remember) = match self.client_logins.get(name) {
+            Some(login) => (login.password.clone(), login.remember),
+            None => (String::new(), false),
+        };
+        self.send(GUIMessage::StartClient {
+            name: name.to_string(),
+            password,
+            remember,
+        });
+    }
+
+    /// Tells the player the game crashed, and lets them look at its output or
+    /// try again
+    fn game_crash_window(&mut self, ctx: &egui::Context) {
+        let crash = match &self.game_crash {
+            Some(crash) => crash,
+            None => return,
+        };
+
+        let mut open = true;
+        let mut retry = false;
+
+        let title = match &crash.client {
+            Some(name) => format!("Client {name} crashed"),
+            None => "The game crashed".to_string(),
+        };
+
+        egui::Window::new(title)
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0., -40.))
+            .show(ctx, |ui| {
+                window_text_styles(ui);
+                ui.set_width(420.);
+
+                let text = if crash.while_starting {
+                    format!("The game closed with {} while it was starting.", crash.exit)
+                } else {
+                    format!("The game closed unexpectedly with {}.", crash.exit)
+                };
+                ui.add(egui::Label::new(text).wrap(true));
+                ui.add(
+                    egui::Label::new(
+                        egui::RichText::new(
+                            "The game's output may explain what went wrong. Checking the \
+                             setup in Settings can also help.",
+                        )
+                        .italics(),
+                    )
+                    .wrap(true),
+                );
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("Show log").clicked() {
+                        if let Err(why) = open::that(&crash.log_path) {
+                            log::warn!("Could not open game log: {why}");
+                        }
+                    }
+                    retry = ui.button("Try again").clicked();
+                });
+            });
+
+        if retry {
+            match crash.client.clone() {
+                Some(name) => self.start_client(&name),
+                None => self.press(GUIMessage::Play),
+            }
+        }
+        if !open || retry {
+            self.game_crash = None;
+        }
+    }
+
+    /// Shows the results of the setup checks, with how to fix any problems
+    fn diagnostics_window(&mut self, ctx: &egui::Context) {
+        let checks = match &self.diagnostics {
+            Some(checks) => checks,
+            None => return,
+        };
+
+        let mut open = true;
+        let mut check_again = false;
+        // The worker only checks once patching is over
+        let can_check = !self.is_patching();
+
+        egui::Window::new("Setup check")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0., -40.))
+            .show(ctx, |ui| {
+                window_text_styles(ui);
+                ui.set_width(480.);
+
+                egui::ScrollArea::vertical()
+                    .max_height(340.)
+                    .show(ui, |ui| {
+                        for check in checks {
+                            let (status, color) = match check.status {
+                                CheckStatus::Passed => {
+                                    ("OK", egui::Color32::from_rgb(0x80, 0xD0, 0x80))
+                                }
+                                CheckStatus::Warning => {
+                                    ("WARNING", egui::Color32::from_rgb(0xF0, 0xD0, 0x90))
+                                }
+                                CheckStatus::Failed => {
+                                    ("FAILED", egui::Color32::from_rgb(0xF0, 0x80, 0x80))
+                                }
+                            };
+
+                            ui.horizontal(|ui| {
+                                ui.label(egui::RichText::new(status).strong().color(color));
+                                ui.label(egui::RichText::new(&check.name).strong());
+                            });
+                            ui.add(egui::Label::new(&check.detail).wrap(true));
+                            if let Some(remedy) = &check.remedy {
+                                ui.add(
+                                    egui::Label::new(egui::RichText::new(remedy).italics())
+                                        .wrap(true),
+                                );
+                            }
+                            ui.add_space(6.);
+                        }
+                    });
+
+                ui.separator();
+                check_again = ui
+                    .add_enabled(can_check, egui::Button::new("Check again"))
+                    .on_disabled_hover_text("The setup can be checked once patching has finished")
+                    .clicked();
+            });
+
+        if check_again {
+            self.send(GUIMessage::RunDiagnostics);
+        }
+        if !open {
+            self.diagnostics = None;
+        }
+    }
+
+    fn settings_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.settings_open;
+        let mut close = false;
+
+        egui::Window::new("Settings")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0., -40.))
+            .show(ctx, |ui| {
+                window_text_styles(ui);
+                ui.set_width(480.);
+
+                // Changes are only kept once they are saved
+                if self.settings_draft.is_none() {
+                    self.settings_draft = self.settings.clone();
+                }
+                if self.settings_draft.is_none() {
+                    ui.label("Loading settings...");
+                    return;
+                }
+
+                egui::ScrollArea::vertical()
+                    .max_height(340.)
+                    .show(ui, |ui| {
+                        self.game_settings(ui);
+                        ui.add_space(10.);
+                        self.game_ini_settings(ui);
+                        self.ini_files_settings(ui);
+                        if cfg!(unix) {
+                            ui.add_space(10.);
+                            self.wine_settings(ui);
+                        }
+                    });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(!self.is_patching(), egui::Button::new("Check setup"))
+                        .on_disabled_hover_text(
+                            "The setup can be checked once patching has finished",
+                        )
+                        .clicked()
+                    {
+                        self.send(GUIMessage::RunDiagnostics);
+                    }
+                    self.export_diagnostics_button(ui);
+                });
+
+                ui.separator();
+                close = self.settings_buttons(ui);
+            });
+
+        self.settings_open = open && !close;
+        if !self.settings_open {
+            self.settings_draft = None;
+            self.game_ini_draft = None;
+            self.new_ini_profile.clear();
+            self.selected_backup = None;
+            self.confirm_reset_prefix = false;
+        }
+    }
+
+    /// Shows the save and cancel buttons. Returns true if the settings window
+    /// should close.
+    fn settings_buttons(&mut self, ui: &mut egui::Ui) -> bool {
+        let problem = if self.is_patching() {
+            Some("Settings can be saved once patching has finished")
+        } else if !self.settings_draft.as_ref().map_or(true, clients_are_valid) {
+            Some("Every client needs a different name")
+        } else if self
+            .game_ini_changes()
+            .iter()
+            .any(|change| ini::validate(&change.section, &change.key, &change.value).is_err())
+        {
+            Some("Some game options have invalid values")
+        } else {
+            None
+        };
+
+        let mut close = false;
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(problem.is_none(), egui::Button::new("Save"))
+                .on_disabled_hover_text(problem.unwrap_or_default())
+                .clicked()
+            {
+                if let Some(mut settings) = self.settings_draft.take() {
+                    settings.wine.runner_env.retain(|_, env| !env.is_empty());
+                    self.send(GUIMessage::SaveSettings(Box::new(settings.clone())));
+                    self.settings = Some(settings);
+                }
+                let changes = self.game_ini_changes();
+                if !changes.is_empty() {
+                    self.send(GUIMessage::SaveGameIni(changes));
+                }
+                close = true;
+            }
+
+            if ui.button("Cancel").clicked() {
+                close = true;
+            }
+        });
+        close
+    }
+
+    /// Whether the worker is patching. It only reads messages between
+    /// patching steps, so changes have to wait until it is done.
+    fn is_patching(&self) -> bool {
+        matches!(
+            self.play_button_state,
+            PlayButtonState::Working | PlayButtonState::Paused
+        )
+    }
+
+    /// Whether the main game or any of the extra clients is running
+    fn is_game_running(&self) -> bool {
+        matches!(self.play_button_state, PlayButtonState::Playing)
+            || !self.running_clients.is_empty()
+    }
+
+    fn game_settings(&mut self, ui: &mut egui::Ui) {
+        let settings = match &mut self.settings_draft {
+            Some(settings) => settings,
+            None => return,
+        };
+
+        ui.heading("Game");
+
+        ui.label("Once the game has started");
+        for (after_launch, text) in [
+            (AfterLaunch::Close, "Close the launcher"),
+            (AfterLaunch::Hide, "Hide the launcher until the game closes"),
+            (AfterLaunch::Stay, "Keep the launcher open"),
+        ] {
+            ui.radio_value(&mut settings.game.after_launch, after_launch, text);
+        }
+
+        ui.add_space(10.);
+        egui::CollapsingHeader::new("Launch options").show(ui, |ui| {
+            ui.label("Extra arguments");
+            let mut removed = None;
+            egui::Grid::new("game_args").num_columns(2).show(ui, |ui| {
+                for (i, arg) in settings.game.extra_args.iter_mut().enumerate() {
+                    ui.text_edit_singleline(arg);
+                    if ui.button("Remove").clicked() {
+                        removed = Some(i);
+                    }
+                    ui.end_row();
+                }
+
+                ui.add(egui::TextEdit::singleline(&mut self.new_game_arg).hint_text("/debug"));
+                let arg = self.new_game_arg.trim();
+                if ui
+                    .add_enabled(!arg.is_empty(), egui::Button::new("Add"))
+                    .clicked()
+                {
+                    settings.game.extra_args.push(arg.to_string());
+                    self.new_game_arg.clear();
+                }
+                ui.end_row();
+            });
+            if let Some(i) = removed {
+                settings.game.extra_args.remove(i);
+            }
+
+            ui.label("Environment variables");
+            env_editor(
+                ui,
+                "game_env",
+                &mut settings.game.env,
+                &mut self.new_game_env_key,
+                &mut self.new_game_env_value,
+            );
+
+            ui.label("Working directory");
+            ui.add(
+                egui::TextEdit::singleline(&mut settings.game.working_dir)
+                    .hint_text(self.game_dir.to_string_lossy())
+                    .desired_width(f32::INFINITY),
+            )
+            .on_hover_text("Relative paths are inside the game directory");
+
+            ui.label("Preview");
+            let preview = LaunchCommand::preview(settings, &self.runners, &self.game_dir);
+            ui.add(egui::Label::new(egui::RichText::new(preview).monospace()).wrap(true));
+        });
+
+        egui::CollapsingHeader::new("Clients").show(ui, |ui| {
+            ui.add(
+                egui::Label::new(
+                    "Extra copies of the game, such as for a support character. They are \
+                     started from the Clients window. A client can start with one of the \
+                     eco.ini profiles from Game options.",
+                )
+                .wrap(true),
+            );
+
+            let clients = &mut settings.game.clients;
+            let profiles = &self.ini_profiles;
+            let mut removed = None;
+            egui::Grid::new("clients_settings")
+                .num_columns(4)
+                .show(ui, |ui| {
+                    ui.label("Name");
+                    ui.label("Account");
+                    ui.label("eco.ini profile");
+                    ui.end_row();
+
+                    for (i, client) in clients.iter_mut().enumerate() {
+                        ui.add(egui::TextEdit::singleline(&mut client.name).desired_width(100.));
+                        ui.add(
+                            egui::TextEdit::singleline(&mut client.username).desired_width(120.),
+                        );
+                        ini_profile_picker(
+                            ui,
+                            ("client_ini_profile", i),
+                            &mut client.ini_profile,
+                            profiles,
+                            100.,
+                        );
+                        if ui.button("Remove").clicked() {
+                            removed = Some(i);
+                        }
+                        ui.end_row();
+                    }
+                });
+            if let Some(i) = removed {
+                clients.remove(i);
+            }
+
+            if ui.button("Add client").clicked() {
+                let number = (clients.len() + 1..)
+                    .find(|number| {
+                        let name = format!("Client {number}");
+                        !clients.iter().any(|client| client.name == name)
+                    })
+                    .expect("There is always a free number");
+                clients.push(ClientSettings {
+                    name: format!("Client {number}"),
+                    ..ClientSettings::default()
+                });
+            }
+        });
+    }
+
+    fn game_ini_changes(&self) -> Vec<IniChange> {
+        match (&self.game_ini, &self.game_ini_draft) {
+            (Some(Ok(ini)), Some(draft)) => game_ini_changes(ini, draft),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Lets the player change options in eco.ini, so they don't have to edit
+    /// it by hand
+    fn game_ini_settings(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Game options");
+
+        match &self.game_ini {
+            None => {
+                ui.label("Game options can be changed once patching has finished.");
+                return;
+            }
+            Some(Err(why)) => {
+                ui.add(egui::Label::new(why.as_str()).wrap(true));
+                return;
+            }
+            Some(Ok(ini)) => {
+                if self.game_ini_draft.is_none() {
+                    self.game_ini_draft = Some(ini.clone());
+                }
+            }
+        }
+        let draft = match &mut self.game_ini_draft {
+            Some(draft) => draft,
+            None => return,
+        };
+
+        // Known options get their own controls, if the game has written them
+        let mut shown_known = false;
+        egui::Grid::new("known_game_options")
+            .num_columns(2)
+            .show(ui, |ui| {
+                for option in KNOWN_OPTIONS {
+                    let value = match draft.get(option.section, option.key) {
+                        Some(value) => value.to_string(),
+                        None => continue,
+                    };
+                    shown_known = true;
+                    ui.label(option.label);
+
+                    let new_value = match option.kind {
+                        OptionKind::Toggle => {
+                            let mut enabled = value.trim() == "1";
+                            ui.checkbox(&mut enabled, "")
+                                .changed()
+                                .then(|| if enabled { "1" } else { "0" }.to_string())
+                        }
+                        OptionKind::Number { min, max } => match value.trim().parse::<i64>() {
+                            Ok(mut number) => {
+                                let changed = if max <= 100 {
+                                    ui.add(egui::Slider::new(&mut number, min..=max)).changed()
+                                } else {
+                                    ui.add(egui::DragValue::new(&mut number).clamp_range(min..=max))
+                                        .changed()
+                                };
+                                changed.then(|| number.to_string())
+                            }
+                            // Let the player fix values which aren't numbers
+                            Err(_) => {
+                                let mut text = value.clone();
+                                ui.text_edit_singleline(&mut text).changed().then_some(text)
+                            }
+                        },
+                    };
+                    ui.end_row();
+
+                    if let Some(new_value) = new_value {
+                        draft.set(option.section, option.key, &new_value);
+                    }
+                }
+            });
+        if !shown_known {
+            ui.label("The game has not saved its options yet.");
+        }
+
+        egui::CollapsingHeader::new("All options").show(ui, |ui| {
+            // A key which is in the file twice can only be edited once, since
+            // the game only reads the first one
+            let mut entries: Vec<(String, String, String)> = Vec::new();
+            for (section, key, value) in draft.entries() {
+                let is_duplicate = entries.iter().any(|(s, k, _)| {
+                    s.eq_ignore_ascii_case(section) && k.eq_ignore_ascii_case(key)
+                });
+                if !is_duplicate {
+                    entries.push((section.to_string(), key.to_string(), value.to_string()));
+                }
+            }
+
+            egui::Grid::new("all_game_options")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    for (section, key, mut value) in entries {
+                        ui.label(format!("[{section}] {key}"));
+                        ui.vertical(|ui| {
+                            if ui.text_edit_singleline(&mut value).changed() {
+                                draft.set(&section, &key, &value);
+                            }
+                            if let Err(why) = ini::validate(&section, &key, &value) {
+                                ui.colored_label(egui::Color32::from_rgb(0xF0, 0x80, 0x80), why);
+                            }
+                        });
+                        ui.end_row();
+                    }
+                });
+        });
+
+        ui.add(
+            egui::Label::new(format!(
+                "eco.ini is saved as {}. Earlier versions can be restored from the backups \
+                 below.",
+                draft.encoding().name()
+            ))
+            .wrap(true),
+        );
+    }
+
+    /// Lets the player save eco.ini as profiles, pick the profile the game
+    /// starts with, and restore backups
+    fn ini_files_settings(&mut self, ui: &mut egui::Ui) {
+        let is_patching = self.is_patching();
+        let settings = match &mut self.settings_draft {
+            Some(settings) => settings,
+            None => return,
+        };
+        // Sent once the settings are no longer borrowed
+        let mut message = None;
+
+        egui::CollapsingHeader::new("Profiles").show(ui, |ui| {
+            ui.add(
+                egui::Label::new(
+                    "Profiles are saved copies of eco.ini, such as one for a laptop and one \
+                     for a big screen. The chosen profile replaces eco.ini each time the \
+                     game starts.",
+                )
+                .wrap(true),
+            );
+
+            ui.horizontal(|ui| {
+                ui.label("Start the game with");
+                ini_profile_picker(
+                    ui,
+                    "game_ini_profile",
+                    &mut settings.game.ini_profile,
+                    &self.ini_profiles,
+                    200.,
+                );
+            });
+
+            egui::Grid::new("ini_profiles")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    for profile in &self.ini_profiles {
+                        ui.label(profile);
+                        if ui
+                            .add_enabled(!is_patching, egui::Button::new("Delete"))
+                            .clicked()
+                        {
+                            message = Some(GUIMessage::DeleteIniProfile(profile.clone()));
+                        }
+                        ui.end_row();
+                    }
+                });
+
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.new_ini_profile)
+                        .hint_text("Profile name")
+                        .desired_width(160.),
+                );
+                let profile = self.new_ini_profile.trim();
+                if ui
+                    .add_enabled(
+                        !profile.is_empty() && !is_patching,
+                        egui::Button::new("Save current eco.ini"),
+                    )
+                    .on_hover_text("Unsaved changes to the game options are not included")
+                    .clicked()
+                {
+                    message = Some(GUIMessage::SaveIniProfile(profile.to_string()));
+                    self.new_ini_profile.clear();
+                }
+            });
+        });
+
+        egui::CollapsingHeader::new("Backups").show(ui, |ui| {
+            ui.add(
+                egui::Label::new(
+                    "eco.ini is backed up whenever the launcher or the game changes it.",
+                )
+                .wrap(true),
+            );
+            if self.ini_backups.is_empty() {
+                ui.label("There are no backups yet.");
+                return;
+            }
+
+            ui.horizontal(|ui| {
+                let selected_text = self
+                    .ini_backups
+                    .iter()
+                    .find(|backup| Some(&backup.name) == self.selected_backup.as_ref())
+                    .map_or("Pick a backup", |backup| backup.saved.as_str())
+                    .to_string();
+                egui::ComboBox::from_id_source("ini_backups")
+                    .selected_text(selected_text)
+                    .width(200.)
+                    .show_ui(ui, |ui| {
+                        for backup in &self.ini_backups {
+                            ui.selectable_value(
+                                &mut self.selected_backup,
+                                Some(backup.name.clone()),
+                                &backup.saved,
+                            );
+                        }
+                    });
+
+                if ui
+                    .add_enabled(
+                        self.selected_backup.is_some() && !is_patching,
+                        egui::Button::new("Restore"),
+                    )
+                    .on_hover_text(
+                        "The current eco.ini is backed up first. A running game saves over \
+                         eco.ini when it closes.",
+                    )
+                    .clicked()
+                {
+                    if let Some(name) = self.selected_backup.take() {
+                        message = Some(GUIMessage::RestoreGameIni(name));
+                    }
+                }
+            });
+        });
+
+        if let Some(message) = message {
+            self.send(message);
+        }
+    }
+
+    fn wine_settings(&mut self, ui: &mut egui::Ui) {
+        let settings = match &mut self.settings_draft {
+            Some(settings) => &mut settings.wine,
+            None => return,
+        };
+
+        ui.heading("Wine");
+
+        // Runner picker
+        let selected_text = match (&settings.runner, runner::selected(&self.runners, settings)) {
+            (None, Some(runner)) => format!("Automatic: {}", runner.label()),
+            (Some(_), Some(runner)) => runner.label(),
+            (_, None) => "No runner was found".to_string(),
+        };
+        egui::ComboBox::from_label("Runner")
+            .selected_text(selected_text)
+            .width(380.)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut settings.runner, None, "Automatic");
+                for runner in &self.runners {
+                    ui.selectable_value(&mut settings.runner, Some(runner.id()), runner.label());
+                }
+            });
+
+        ui.label("Custom runner");
+        ui.add(
+            egui::TextEdit::singleline(&mut settings.custom_runner)
+                .hint_text("/path/to/bin/wine")
+                .desired_width(f32::INFINITY),
+        )
+        .on_hover_text(
+            "A wine binary which isn't found automatically. It can be picked after saving.",
+        );
+
+        // Environment variables for the runner which is used
+        if let Some(runner) = runner::selected(&self.runners, settings) {
+            ui.label(format!("Environment variables for {}", runner.name));
+
+            env_editor(
+                ui,
+                "runner_env",
+                settings.runner_env.entry(runner.id()).or_default(),
+                &mut self.new_env_key,
+                &mut self.new_env_value,
+            );
+        }
+
+        ui.add_space(10.);
+        ui.add(
+            egui::Label::new(
+                "The game runs in its own Wine prefix inside the game directory. \
+                 Resetting it can fix a game which no longer starts.",
+            )
+            .wrap(true),
+        );
+
+        let reset_text = if self.confirm_reset_prefix {
+            "Click again to reset"
+        } else {
+            "Reset Wine prefix"
+        };
+        // The prefix can't be removed from under a running game
+        let can_reset = !self.is_patching() && !self.is_game_running();
+        if !can_reset {
+            self.confirm_reset_prefix = false;
+        }
+        if ui
+            .add_enabled(can_reset, egui::Button::new(reset_text))
+            .on_disabled_hover_text(
+                "The Wine prefix can be reset once patching has finished and the game and \
+                 its clients are closed",
+            )
+            .clicked()
+        {
+            if self.confirm_reset_prefix {
+                self.send(GUIMessage::ResetWinePrefix);
+            }
+            self.confirm_reset_prefix = !self.confirm_reset_prefix;
+        }
+    }
+
+    fn warning_banner(&mut self, ui: &mut egui::Ui) {
+        let text = match &self.warning {
+            Some(text) => text,
+            None => return,
+        };
+
+        egui::TopBottomPanel::top("warning_banner")
+            .frame(
+                egui::Frame::none()
+                    .fill(egui::Color32::from_rgb(0xF0, 0xD0, 0x90))
+                    .inner_margin(10.),
+            )
+            .show_inside(ui, |ui| {
+                ui.style_mut().text_styles = [(
+                    egui::TextStyle::Body,
+                    egui::FontId::new(20.0, egui::FontFamily::Proportional),
+                )]
+                .into();
+                ui.style_mut().visuals.override_text_color = Some(egui::Color32::DARK_GRAY);
+
+                ui.vertical_centered(|ui| {
+                    ui.add(egui::Label::new(text.as_str()).wrap(true));
+                });
+            });
+    }
+
+    /// Shows the message of the day and a countdown for maintenance
+    fn notice_panel(&mut self, ui: &mut egui::Ui) {
+        let notice = match &self.server_notice {
+            Some(notice) => notice,
+            None => return,
+        };
+
+        let now = SystemTime::now();
+        let until = |time: SystemTime| time.duration_since(now).ok();
+        let maintenance_text = match (
+            notice.maintenance_start.and_then(until),
+            notice.maintenance_end.and_then(until),
+        ) {
+            (Some(to_start), _) => Some(format!(
+                "Maintenance begins in {}",
+                countdown_string(to_start)
+            )),
+            (None, Some(to_end)) => Some(format!(
+                "Maintenance is expected to end in {}",
+                countdown_string(to_end)
+            )),
+            _ => None,
+        };
+
+        if notice.motd.is_none() && maintenance_text.is_none() {
+            return;
+        }
+
+        egui::TopBottomPanel::top("notice_panel")
+            .frame(
+                egui::Frame::none()
+                    .fill(self.link_bar_color)
+                    .inner_margin(10.),
+            )
+            .show_inside(ui, |ui| {
+                ui.style_mut().text_styles = [(
+                    egui::TextStyle::Body,
+                    egui::FontId::new(20.0, egui::FontFamily::Proportional),
+                )]
+                .into();
+                ui.style_mut().visuals.override_text_color = Some(egui::Color32::WHITE);
+
+                ui.vertical_centered(|ui| {
+                    if let Some(motd) = &notice.motd {
+                        ui.add(egui::Label::new(motd.as_str()).wrap(true));
+                    }
+                    if let Some(maintenance_text) = maintenance_text {
+                        ui.label(maintenance_text);
+                    }
+                });
+            });
+    }
+
+    /// Shows the news and patch notes from the patch server
+    fn news_panel(&mut self, ui: &mut egui::Ui) {
+        let news = match &self.news {
+            Some(news) if !news.items.is_empty() => news,
+            _ => return,
+        };
+        let textures = &mut self.news_textures;
+
+        egui::SidePanel::right("news_panel")
+            .resizable(false)
+            .min_width(340.)
+            .max_width(340.)
+            .frame(
+                egui::Frame::none()
+                    .fill(self.link_bar_color)
+                    .inner_margin(15.),
+            )
+            .show_inside(ui, |ui| {
+                ui.style_mut().text_styles = [
+                    // Item titles
+                    (
+                        egui::TextStyle::Heading,
+                        egui::FontId::new(24.0, egui::FontFamily::Proportional),
+                    ),
+                    (
+                        egui::TextStyle::Body,
+                        egui::FontId::new(16.0, egui::FontFamily::Proportional),
+                    ),
+                    // Item dates
+                    (
+                        egui::TextStyle::Small,
+                        egui::FontId::new(13.0, egui::FontFamily::Proportional),
+                    ),
+                ]
+                .into();
+                ui.style_mut().visuals.override_text_color = Some(egui::Color32::WHITE);
+                ui.style_mut().visuals.hyperlink_color = egui::Color32::from_rgb(0x90, 0xC0, 0xF0);
+
+                egui::ScrollArea::vertical()
+                    .auto_shrink([false; 2])
+                    .show(ui, |ui| {
+                        for (item, blocks) in news.items.iter().zip(&self.news_blocks) {
+                            ui.horizontal_wrapped(|ui| {
+                                if item.is_new {
+                                    ui.label(
+                                        egui::RichText::new(" NEW ")
+                                            .strong()
+                                            .color(egui::Color32::DARK_GRAY)
+                                            .background_color(egui::Color32::from_rgb(
+                                                0xF0, 0xD0, 0x90,
+                                            )),
+                                    );
+                                }
+                                ui.heading(&item.title);
+                            });
+
+                            if let Some(date) = item.date.and_then(date_string) {
+                                ui.small(date);
+                            }
+
+                            for block in blocks {
+                                news_block(ui, block, &news.images, textures);
+                            }
+
+                            ui.separator();
+                        }
+                    });
+            });
     }
 
     fn login_panel(&mut self, ui: &mut egui::Ui) {
+        // Center the panel in whatever space the other panels left over
+        let side_margin = ((ui.available_width() - 300.) / 2.).max(20.);
+
         egui::TopBottomPanel::top("login_panel_top")
             .frame(
                 egui::Frame::none()
                     .fill(egui::Color32::WHITE)
                     .outer_margin(egui::style::Margin {
-                        left: 350.,
-                        right: 350.,
-                        top: 100.,
-                        bottom: 100.,
+                        left: side_margin,
+                        right: side_margin,
+                        top: 60.,
+                        bottom: 60.,
                     })
                     .inner_margin(20.)
                     .rounding(25.)
@@ -247,14 +1667,94 @@ impl PatcherUI {
                 );
 
                 ui.add(egui::Label::new("Password"));
-                ui.add(
+                let password = ui.add(
                     egui::TextEdit::singleline(&mut self.password)
                         .text_color(egui::Color32::BLACK)
                         .password(true),
                 );
+
+                // Pressing enter in the password field is the same as PLAY
+                if password.lost_focus() && ui.input().key_pressed(egui::Key::Enter) {
+                    self.press(GUIMessage::Play);
+                }
+
+                ui.horizontal(|ui| {
+                    ui.style_mut().text_styles = [
+                        (
+                            egui::TextStyle::Body,
+                            egui::FontId::new(18.0, egui::FontFamily::Proportional),
+                        ),
+                        (
+                            egui::TextStyle::Button,
+                            egui::FontId::new(18.0, egui::FontFamily::Proportional),
+                        ),
+                    ]
+                    .into();
+
+                    ui.checkbox(&mut self.remember_login, "Remember me");
+
+                    if self.has_saved_login && ui.link("Forget").clicked() {
+                        self.forget_login();
+                    }
+                });
+
+                let status = if self.logging_in {
+                    Some(egui::RichText::new("Logging in...").color(egui::Color32::DARK_GRAY))
+                } else {
+                    self.login_error.as_ref().map(|message| {
+                        egui::RichText::new(message)
+                            .color(egui::Color32::from_rgb(0xC0, 0x40, 0x40))
+                    })
+                };
+                if let Some(status) = status {
+                    ui.add(egui::Label::new(status.size(18.)).wrap(true));
+                }
             });
     }
 
+    /// Logs in with the entered username and password. The worker starts the
+    /// game once the login succeeds.
+    fn login(&mut self) {
+        if self.username.is_empty() || self.password.is_empty() {
+            self.login_error = Some("Enter your username and password".to_string());
+            return;
+        }
+
+        self.logging_in = true;
+        self.login_error = None;
+        self.send(GUIMessage::Login {
+            username: self.username.clone(),
+            password: self.password.clone(),
+            remember: self.remember_login,
+        });
+    }
+
+    /// Removes the remembered login and clears the login fields
+    fn forget_login(&mut self) {
+        self.send(GUIMessage::ForgetLogin);
+        self.username.clear();
+        self.password.clear();
+        self.remember_login = false;
+        self.has_saved_login = false;
+    }
+
+    /// Handles a button press. With login turned on, PLAY logs in first.
+    fn press(&mut self, message: GUIMessage) {
+        match message {
+            GUIMessage::Play if self.use_login => {
+                // Only one login at a time, and only when the game can be played
+                let can_play = matches!(
+                    self.play_button_state,
+                    PlayButtonState::Play | PlayButtonState::Offline
+                );
+                if can_play && !self.logging_in {
+                    self.login();
+                }
+            }
+            message => self.send(message),
+        }
+    }
+
     fn bottom_panel(&mut self, ui: &mut egui::Ui) {
         egui::TopBottomPanel::bottom("bottom_panel")
             .frame(
@@ -275,7 +1775,7 @@ impl PatcherUI {
             });
     }
 
-    fn links_panel(&self, ui: &mut egui::Ui) {
+    fn links_panel(&mut self, ui: &mut egui::Ui) {
         egui::TopBottomPanel::top("links_panel")
             .frame(egui::Frame::none().inner_margin(15.))
             .show_inside(ui, |ui| {
@@ -297,27 +1797,43 @@ impl PatcherUI {
                     ui.style_mut().visuals.widgets.noninteractive.bg_stroke =
                         egui::Stroke::new(1., egui::Color32::GRAY);
 
-                    // Control panel link
-                    if ui
-                        .add(egui::Button::new("Control Panel").fill(egui::Color32::TRANSPARENT))
-                        .clicked()
-                    {
-                        open::that("https://ecocp.atomixro.com").ok();
-                    }
-
-                    ui.separator();
+                    for (i, link) in self.links.iter().enumerate() {
+                        if i > 0 {
+                            ui.separator();
+                        }
 
-                    // Registration link
-                    if ui
-                        .add(egui::Button::new("Register").fill(egui::Color32::TRANSPARENT))
-                        .clicked()
-                    {
-                        open::that("https://ecocp.atomixro.com/register").ok();
+                        if ui
+                            .add(egui::Button::new(&link.label).fill(egui::Color32::TRANSPARENT))
+                            .clicked()
+                        {
+                            open::that(&link.url).ok();
+                        }
                     }
 
                     // Version string
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Max), |ui| {
                         ui.label(&self.program_version);
+
+                        if ui
+                            .add(egui::Button::new("Settings").fill(egui::Color32::TRANSPARENT))
+                            .clicked()
+                        {
+                            self.settings_open = !self.settings_open;
+                        }
+
+                        if ui
+                            .add(egui::Button::new("Clients").fill(egui::Color32::TRANSPARENT))
+                            .clicked()
+                        {
+                            self.clients_open = !self.clients_open;
+                        }
+
+                        if ui
+                            .add(egui::Button::new("Log").fill(egui::Color32::TRANSPARENT))
+                            .clicked()
+                        {
+                            self.log_open = !self.log_open;
+                        }
                     });
                 });
             });
@@ -370,12 +1886,22 @@ impl PatcherUI {
 
     fn send(&self, message: GUIMessage) {
         if let Err(why) = self.tx.send(message) {
-            eprintln!("Could not send message from GUI to PatchWorker: {why}");
+            log::error!("Could not send message from GUI to PatchWorker: {why}");
         }
     }
 
     fn play_button(&mut self, ui: &mut egui::Ui) {
         let rounding = 25.;
+
+        if self.logging_in {
+            ui.add(
+                atomix::RoundButton::new("WAIT")
+                    .rounding(rounding)
+                    .sense(egui::Sense::hover()),
+            );
+            return;
+        }
+
         match self.play_button_state {
             PlayButtonState::Disabled => {
                 ui.add(
@@ -384,12 +1910,29 @@ impl PatcherUI {
                         .sense(egui::Sense::hover()),
                 );
             }
+            PlayButtonState::Working => {
+                self.split_buttons(
+                    ui,
+                    ("PAUSE", GUIMessage::Pause),
+                    ("CANCEL", GUIMessage::Cancel),
+                );
+            }
+            PlayButtonState::Paused => {
+                self.split_buttons(
+                    ui,
+                    ("RESUME", GUIMessage::Resume),
+                    ("CANCEL", GUIMessage::Cancel),
+                );
+            }
+            PlayButtonState::Offline => {
+                self.split_buttons(ui, ("PLAY", GUIMessage::Play), ("RETRY", GUIMessage::Retry));
+            }
             PlayButtonState::Play => {
                 if ui
                     .add(atomix::RoundButton::new("PLAY").rounding(rounding))
                     .clicked()
                 {
-                    self.send(GUIMessage::Play);
+                    self.press(GUIMessage::Play);
                 }
             }
             PlayButtonState::Retry => {
@@ -400,9 +1943,65 @@ impl PatcherUI {
                     self.send(GUIMessage::Retry);
                 }
             }
+            PlayButtonState::Playing => {
+                // The usual size does not fit
+                ui.style_mut().text_styles = [(
+                    egui::TextStyle::Button,
+                    egui::FontId::new(40.0, egui::FontFamily::Proportional),
+                )]
+                .into();
+                ui.add(
+                    atomix::RoundButton::new("PLAYING")
+                        .rounding(rounding)
+                        .sense(egui::Sense::hover()),
+                );
+            }
         };
     }
 
+    /// Shows two buttons above each other, in the space that is normally
+    /// taken up by the play button
+    fn split_buttons(
+        &mut self,
+        ui: &mut egui::Ui,
+        (top_text, top_message): (&str, GUIMessage),
+        (bottom_text, bottom_message): (&str, GUIMessage),
+    ) {
+        ui.style_mut().text_styles = [(
+            egui::TextStyle::Button,
+            egui::FontId::new(26.0, egui::FontFamily::Proportional),
+        )]
+        .into();
+
+        let button_height = (ui.available_height() - ui.spacing().item_spacing.y) / 2.;
+        let button_size = egui::vec2(0., button_height);
+        let rounding = button_height / 2.;
+
+        ui.vertical_centered_justified(|ui| {
+            if ui
+                .add(
+                    atomix::RoundButton::new(top_text)
+                        .rounding(rounding)
+                        .min_size(button_size),
+                )
+                .clicked()
+            {
+                self.press(top_message);
+            }
+
+            if ui
+                .add(
+                    atomix::RoundButton::new(bottom_text)
+                        .rounding(rounding)
+                        .min_size(button_size),
+                )
+                .clicked()
+            {
+                self.press(bottom_message);
+            }
+        });
+    }
+
     fn patch_progress_bar(&mut self, ui: &mut egui::Ui) {
         // Progress bar primary color
         ui.style_mut().visuals.selection.bg_fill = self.progress_bar_state.foreground_color();
@@ -414,12 +2013,218 @@ impl PatcherUI {
         )]
         .into();
         ui.style_mut().visuals.override_text_color = Some(self.progress_bar_state.text_color());
-        ui.add(
+        let response = ui.add(
             atomix::ProgressBar::new(self.progress_bar_state.amount())
                 .height(72.)
                 .rounding(25.)
                 .text(self.progress_bar_state.text()),
         );
+
+        // Errors with details can be clicked to show them
+        let has_details = matches!(self.progress_bar_state, ProgressBarState::Error(_))
+            && self.error_details.is_some();
+        if has_details {
+            let response = ui
+                .interact(response.rect, response.id, egui::Sense::click())
+                .on_hover_cursor(egui::CursorIcon::PointingHand)
+                .on_hover_text("Show details");
+            if response.clicked() {
+                self.error_details_open = true;
+            }
+        }
+    }
+}
+
+/// Checks that every client has a name, and that no two have the same name
+fn clients_are_valid(settings: &Settings) -> bool {
+    let clients = &settings.game.clients;
+    clients.iter().enumerate().all(|(i, client)| {
+        !client.name.trim().is_empty() && !clients[..i].iter().any(|c| c.name == client.name)
+    })
+}
+
+/// Lists the values which were changed in the settings window. Only the
+/// first of a key which is in the file twice can be changed.
+fn game_ini_changes(ini: &IniFile, draft: &IniFile) -> Vec<IniChange> {
+    let mut changes: Vec<IniChange> = Vec::new();
+    for (section, key, _) in draft.entries() {
+        let is_duplicate = changes.iter().any(|change| {
+            change.section.eq_ignore_ascii_case(section) && change.key.eq_ignore_ascii_case(key)
+        });
+        let value = draft.get(section, key);
+        if !is_duplicate && value != ini.get(section, key) {
+            changes.push(IniChange {
+                section: section.to_string(),
+                key: key.to_string(),
+                value: value.unwrap_or_default().to_string(),
+            });
+        }
+    }
+    changes
+}
+
+/// Names a level of the log viewer, which shows that level and the ones
+/// more severe than it
+fn log_level_name(level: log::Level) -> &'static str {
+    match level {
+        log::Level::Error => "Errors",
+        log::Level::Warn => "Warnings",
+        log::Level::Info => "Information",
+        log::Level::Debug | log::Level::Trace => "Everything",
+    }
+}
+
+/// Picks one of the eco.ini profiles, or none to use the current eco.ini
+fn ini_profile_picker(
+    ui: &mut egui::Ui,
+    id_source: impl std::hash::Hash,
+    profile: &mut String,
+    profiles: &[String],
+    width: f32,
+) {
+    let selected_text = match profile.as_str() {
+        "" => "Current eco.ini".to_string(),
+        name => name.to_string(),
+    };
+    egui::ComboBox::from_id_source(id_source)
+        .selected_text(selected_text)
+        .width(width)
+        .show_ui(ui, |ui| {
+            ui.selectable_value(profile, String::new(), "Current eco.ini");
+            for name in profiles {
+                ui.selectable_value(profile, name.clone(), name);
+            }
+        });
+}
+
+/// Lets the player edit environment variables. `new_key` and `new_value` hold
+/// the variable being added.
+fn env_editor(
+    ui: &mut egui::Ui,
+    id: &str,
+    env: &mut BTreeMap<String, String>,
+    new_key: &mut String,
+    new_value: &mut String,
+) {
+    let mut removed = None;
+    egui::Grid::new(id).num_columns(3).show(ui, |ui| {
+        for (key, value) in env.iter_mut() {
+            ui.label(key.as_str());
+            ui.text_edit_singleline(value);
+            if ui.button("Remove").clicked() {
+                removed = Some(key.clone());
+            }
+            ui.end_row();
+        }
+
+        ui.add(egui::TextEdit::singleline(new_key).hint_text("WINEDEBUG"));
+        ui.add(egui::TextEdit::singleline(new_value).hint_text("-all"));
+        let key = new_key.trim();
+        if ui
+            .add_enabled(!key.is_empty(), egui::Button::new("Add"))
+            .clicked()
+        {
+            env.insert(key.to_string(), new_value.clone());
+            new_key.clear();
+            new_value.clear();
+        }
+        ui.end_row();
+    });
+
+    if let Some(key) = removed {
+        env.remove(&key);
+    }
+}
+
+/// Text sizes for windows shown over the launcher
+fn window_text_styles(ui: &mut egui::Ui) {
+    ui.style_mut().text_styles = [
+        (
+            egui::TextStyle::Heading,
+            egui::FontId::new(22.0, egui::FontFamily::Proportional),
+        ),
+        (
+            egui::TextStyle::Body,
+            egui::FontId::new(16.0, egui::FontFamily::Proportional),
+        ),
+        (
+            egui::TextStyle::Button,
+            egui::FontId::new(16.0, egui::FontFamily::Proportional),
+        ),
+        (
+            egui::TextStyle::Monospace,
+            egui::FontId::new(14.0, egui::FontFamily::Monospace),
+        ),
+    ]
+    .into();
+}
+
+fn news_block(
+    ui: &mut egui::Ui,
+    block: &Block,
+    images: &HashMap<String, Vec<u8>>,
+    textures: &mut HashMap<String, Option<egui::TextureHandle>>,
+) {
+    match block {
+        Block::Heading(text) => {
+            ui.label(egui::RichText::new(text).strong().size(19.));
+        }
+        Block::Bullet(inlines) => {
+            ui.horizontal_wrapped(|ui| {
+                ui.label("•");
+                news_inlines(ui, inlines);
+            });
+        }
+        Block::Image(src) => {
+            let texture = textures
+                .entry(src.clone())
+                .or_insert_with(|| load_news_texture(ui, src, images));
+
+            if let Some(texture) = texture {
+                // Shrink images which don't fit, but never stretch them
+                let size = texture.size_vec2();
+                let scale = (ui.available_width() / size.x).min(1.);
+                ui.image(&*texture, size * scale);
+            }
+        }
+        Block::Paragraph(inlines) => {
+            ui.horizontal_wrapped(|ui| news_inlines(ui, inlines));
+        }
+    }
+}
+
+fn news_inlines(ui: &mut egui::Ui, inlines: &[Inline]) {
+    for inline in inlines {
+        match inline {
+            // Only web links are parsed, so news can't start programs
+            Inline::Link { text, url } => {
+                if ui.link(text).on_hover_text(url).clicked() {
+                    open::that(url).ok();
+                }
+            }
+            Inline::Text(text) => {
+                ui.label(text);
+            }
+        }
+    }
+}
+
+fn load_news_texture(
+    ui: &egui::Ui,
+    src: &str,
+    images: &HashMap<String, Vec<u8>>,
+) -> Option<egui::TextureHandle> {
+    let image_data = images.get(src)?;
+    match load_image_from_memory(image_data) {
+        Ok(image) => Some(ui.ctx().load_texture(
+            format!("news:{src}"),
+            image,
+            egui::TextureFilter::Linear,
+        )),
+        Err(why) => {
+            log::warn!("Could not load news image {src:?}: {why}");
+            None
+        }
     }
 }
 
@@ -59,3 +59,49 @@ pub fn version_summary() -> String {
 
     pieces.join("-")
 }
+
+/// Compares two dotted version strings such as `1.0.1` and `1.2`, number by
+/// number. Missing numbers count as 0, and anything after a `-` is ignored.
+pub fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    fn numbers(version: &str) -> Vec<u64> {
+        let release = version.split('-').next().unwrap_or(version);
+        release
+            .split('.')
+            .map(|number| number.trim().parse().unwrap_or(0))
+            .collect()
+    }
+
+    let a = numbers(a);
+    let b = numbers(b);
+    let len = a.len().max(b.len());
+    let a = a.into_iter().chain(std::iter::repeat(0)).take(len);
+    let b = b.into_iter().chain(std::iter::repeat(0)).take(len);
+    a.cmp(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::compare_versions;
+    use std::cmp::Ordering;
+
+    #[test]
+    fn compares_numbers_not_text() {
+        assert_eq!(compare_versions("1.2", "1.10"), Ordering::Less);
+        assert_eq!(compare_versions("1.10", "1.9.9"), Ordering::Greater);
+        assert_eq!(compare_versions("2.0", "10.0"), Ordering::Less);
+    }
+
+    #[test]
+    fn missing_numbers_are_zero() {
+        assert_eq!(compare_versions("1.2.0", "1.2"), Ordering::Equal);
+        assert_eq!(compare_versions("1.2", "1.2.0.0"), Ordering::Equal);
+        assert_eq!(compare_versions("1.0.1", "1.0"), Ordering::Greater);
+    }
+
+    #[test]
+    fn ignores_suffixes() {
+        assert_eq!(compare_versions("1.2.0-beta", "1.2"), Ordering::Equal);
+        assert_eq!(compare_versions("1.0.1", "1.0.1-abc123"), Ordering::Equal);
+        assert_eq!(compare_versions("1.0.1", "1.0.2-rc1"), Ordering::Less);
+    }
+}